@@ -0,0 +1,337 @@
+//! Allowlist of known synthetic values and fixture paths.
+//!
+//! Taxonomy §7.4 recommends allowlisting test data that carries intentional fake PHI
+//! rather than lowering global precision. An allowlist file lists entries, each
+//! matching in exactly one way and optionally scoped to pattern IDs:
+//!
+//! ```yaml
+//! salt: "org-fixtures-2026"
+//! entries:
+//!   - value: "0000000000000000"
+//!     patterns: [indonesian_nik]
+//!     reason: "synthetic NIK (taxonomy §0.4)"
+//!   - sha256: "<64 hex digits>"
+//!   - regex: "^9{16}$"
+//!     patterns: [indonesian_nik]
+//!   - path: "tests/fixtures/**"
+//! ```
+//!
+//! `sha256` entries hold the hex SHA-256 of `salt` followed by the value, so the
+//! allowlist itself never contains the value. `regex` entries are compiled within the
+//! same [`RegexLimits`] as the catalogue. `path` globs match the file's path relative
+//! to the repository root (see [`Allowlist::with_glob_root`]).
+
+use crate::domain::{Finding, RegexLimits};
+use crate::error::HealthwandError;
+use crate::file_source::{absolute, relative_to};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AllowlistFile {
+    #[serde(default)]
+    salt: Option<String>,
+    #[serde(default)]
+    entries: Vec<EntryConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EntryConfig {
+    value: Option<String>,
+    sha256: Option<String>,
+    regex: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    patterns: Vec<String>,
+    reason: Option<String>,
+}
+
+#[derive(Debug)]
+enum Matcher {
+    Value(String),
+    Sha256(String),
+    Regex(Regex),
+    Path(GlobMatcher),
+}
+
+#[derive(Debug)]
+struct Entry {
+    matcher: Matcher,
+    /// Pattern IDs in scope; empty means every pattern.
+    patterns: Vec<String>,
+    reason: Option<String>,
+}
+
+/// The allowlist entry that matched a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllowlistMatch<'a> {
+    /// 1-based position of the entry in the file.
+    pub entry: usize,
+    pub reason: Option<&'a str>,
+}
+
+/// A loaded allowlist.
+#[derive(Debug, Default)]
+pub struct Allowlist {
+    salt: String,
+    entries: Vec<Entry>,
+    glob_root: PathBuf,
+}
+
+impl Allowlist {
+    /// Load an allowlist from a YAML file, compiling `regex` entries within `limits`.
+    pub fn from_yaml_file<P: AsRef<Path>>(
+        path: P,
+        limits: &RegexLimits,
+    ) -> crate::error::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Self::from_yaml_str(&text, limits)
+            .map_err(|e| HealthwandError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parse an allowlist from YAML text, compiling `regex` entries within `limits`.
+    pub fn from_yaml_str(text: &str, limits: &RegexLimits) -> crate::error::Result<Self> {
+        let file: AllowlistFile =
+            serde_yaml_ng::from_str(text).map_err(|e| HealthwandError::YamlError(e.to_string()))?;
+        let entries = file
+            .entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                entry
+                    .into_entry(file.salt.is_some(), i, limits)
+                    .map_err(|e| at_entry(i, e))
+            })
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        Ok(Self {
+            salt: file.salt.unwrap_or_default(),
+            entries,
+            glob_root: PathBuf::from("."),
+        })
+    }
+
+    /// Directory `path` globs are relative to (the repository root); defaults to the
+    /// working directory.
+    pub fn with_glob_root<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.glob_root = absolute(dir.as_ref());
+        self
+    }
+
+    /// Salted hash of `value` as written in `sha256` entries.
+    pub fn hash_value(salt: &str, value: &str) -> String {
+        let digest = Sha256::new()
+            .chain_update(salt.as_bytes())
+            .chain_update(value.as_bytes())
+            .finalize();
+        crate::fingerprint::hex(&digest)
+    }
+
+    /// Whether `finding`, found in the file at `path`, is allowlisted.
+    pub fn allows(&self, path: &Path, finding: &Finding) -> bool {
+        self.matching(path, finding).is_some()
+    }
+
+    /// The first entry that allowlists `finding`, found in the file at `path`.
+    pub fn matching(&self, path: &Path, finding: &Finding) -> Option<AllowlistMatch<'_>> {
+        let mut hash = None;
+        let i = self.entries.iter().position(|entry| {
+            let in_scope = entry.patterns.is_empty()
+                || entry
+                    .patterns
+                    .iter()
+                    .any(|id| id == finding.pattern_id.as_str());
+            in_scope
+                && match &entry.matcher {
+                    Matcher::Value(value) => *value == finding.matched_text,
+                    Matcher::Sha256(expected) => {
+                        let actual = hash.get_or_insert_with(|| {
+                            Self::hash_value(&self.salt, &finding.matched_text)
+                        });
+                        expected == actual
+                    }
+                    Matcher::Regex(regex) => regex.is_match(&finding.matched_text),
+                    Matcher::Path(glob) => glob.is_match(relative_to(&self.glob_root, path)),
+                }
+        })?;
+        Some(AllowlistMatch {
+            entry: i + 1,
+            reason: self.entries[i].reason.as_deref(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl EntryConfig {
+    fn into_entry(
+        self,
+        has_salt: bool,
+        index: usize,
+        limits: &RegexLimits,
+    ) -> crate::error::Result<Entry> {
+        let matcher = match (self.value, self.sha256, self.regex, self.path) {
+            (Some(value), None, None, None) => Matcher::Value(value),
+            (None, Some(hash), None, None) => {
+                if !has_salt {
+                    return Err(HealthwandError::ConfigError(
+                        "sha256 entries require a top-level salt".to_string(),
+                    ));
+                }
+                let hash = hash.to_lowercase();
+                if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(HealthwandError::ConfigError(format!(
+                        "invalid sha256 digest '{}'",
+                        hash
+                    )));
+                }
+                Matcher::Sha256(hash)
+            }
+            (None, None, Some(regex), None) => {
+                Matcher::Regex(limits.compile(&format!("allowlist entry {}", index + 1), &regex)?)
+            }
+            (None, None, None, Some(path)) => Matcher::Path(
+                Glob::new(&path)
+                    .map_err(|e| HealthwandError::ConfigError(e.to_string()))?
+                    .compile_matcher(),
+            ),
+            _ => {
+                return Err(HealthwandError::ConfigError(
+                    "entry must set exactly one of value, sha256, regex or path".to_string(),
+                ));
+            }
+        };
+        Ok(Entry {
+            matcher,
+            patterns: self.patterns,
+            reason: self.reason,
+        })
+    }
+}
+
+fn at_entry(index: usize, err: HealthwandError) -> HealthwandError {
+    HealthwandError::ConfigError(format!("allowlist entry {}: {}", index + 1, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_and_regex_entries_respect_scope() {
+        let allowlist = Allowlist::from_yaml_str(
+            r#"
+entries:
+  - value: "0000000000000000"
+    patterns: [indonesian_nik]
+  - regex: "^9{16}$"
+"#,
+            &RegexLimits::default(),
+        )
+        .unwrap();
+        let path = Path::new("notes.txt");
+        assert!(allowlist.allows(
+            path,
+            &Finding::for_test("indonesian_nik", "0000000000000000", "0000000000000000")
+        ));
+        assert!(!allowlist.allows(
+            path,
+            &Finding::for_test("mrn-generic", "0000000000000000", "0000000000000000")
+        ));
+        assert!(allowlist.allows(
+            path,
+            &Finding::for_test("mrn-generic", "9999999999999999", "9999999999999999")
+        ));
+        assert!(!allowlist.allows(
+            path,
+            &Finding::for_test("indonesian_nik", "3171234567890123", "3171234567890123")
+        ));
+    }
+
+    #[test]
+    fn test_salted_hash_entry() {
+        let hash = Allowlist::hash_value("pepper", "123-45-6789");
+        let yaml = format!("salt: pepper\nentries:\n  - sha256: \"{}\"\n", hash);
+        let allowlist = Allowlist::from_yaml_str(&yaml, &RegexLimits::default()).unwrap();
+        assert!(!yaml.contains("123-45-6789"));
+        assert!(allowlist.allows(
+            Path::new("a.txt"),
+            &Finding::for_test("ssn", "123-45-6789", "123-45-6789")
+        ));
+        assert!(!allowlist.allows(
+            Path::new("a.txt"),
+            &Finding::for_test("ssn", "987-65-4321", "987-65-4321")
+        ));
+    }
+
+    #[test]
+    fn test_path_glob_entry() {
+        let allowlist = Allowlist::from_yaml_str(
+            "entries:\n  - path: \"**/fixtures/**\"\n",
+            &RegexLimits::default(),
+        )
+        .unwrap();
+        let f = Finding::for_test("ssn", "123-45-6789", "123-45-6789");
+        assert!(allowlist.allows(Path::new("tests/fixtures/patients.csv"), &f));
+        assert!(!allowlist.allows(Path::new("src/main.rs"), &f));
+    }
+
+    #[test]
+    fn test_invalid_entries_rejected() {
+        let ambiguous = "entries:\n  - value: a\n    regex: b\n";
+        match Allowlist::from_yaml_str(ambiguous, &RegexLimits::default()).unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("entry 1")),
+            _ => panic!("Expected ConfigError"),
+        }
+        let unsalted = format!("entries:\n  - sha256: \"{}\"\n", "a".repeat(64));
+        match Allowlist::from_yaml_str(&unsalted, &RegexLimits::default()).unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("salt")),
+            _ => panic!("Expected ConfigError"),
+        }
+    }
+
+    #[test]
+    fn test_match_reports_entry_and_reason() {
+        let allowlist = Allowlist::from_yaml_str(
+            "entries:\n  - path: \"docs/**\"\n  - value: \"0000000000000000\"\n    reason: synthetic NIK\n",
+            &RegexLimits::default(),
+        )
+        .unwrap();
+        let f = Finding::for_test("indonesian_nik", "0000000000000000", "0000000000000000");
+        assert_eq!(
+            allowlist.matching(Path::new("notes.txt"), &f),
+            Some(AllowlistMatch {
+                entry: 2,
+                reason: Some("synthetic NIK"),
+            })
+        );
+        assert_eq!(
+            allowlist.matching(Path::new("docs/a.md"), &f),
+            Some(AllowlistMatch {
+                entry: 1,
+                reason: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_regex_entries_respect_limits() {
+        let yaml = "entries:\n  - regex: \"[0-9]{200}\"\n";
+        let limits = RegexLimits {
+            size_limit: 1024,
+            ..RegexLimits::default()
+        };
+        assert!(Allowlist::from_yaml_str(yaml, &RegexLimits::default()).is_ok());
+        match Allowlist::from_yaml_str(yaml, &limits).unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("entry 1"), "{}", msg),
+            e => panic!("Expected ConfigError, got {:?}", e),
+        }
+    }
+}
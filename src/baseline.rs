@@ -0,0 +1,180 @@
+//! Baseline of accepted findings, so CI only fails on new ones.
+//!
+//! A baseline records each finding's file path, pattern ID and [fingerprint](crate::fingerprint),
+//! never the matched text. Entries are a multiset: two identical findings in one file
+//! need two entries.
+
+use crate::error::HealthwandError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+const BASELINE_VERSION: u32 = 1;
+
+/// One accepted finding.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub file_path: String,
+    pub pattern_id: String,
+    pub fingerprint: String,
+}
+
+/// A baseline file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub findings: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn new(mut findings: Vec<BaselineEntry>) -> Self {
+        findings.sort_by(|a, b| {
+            (&a.file_path, &a.pattern_id, &a.fingerprint).cmp(&(
+                &b.file_path,
+                &b.pattern_id,
+                &b.fingerprint,
+            ))
+        });
+        Self {
+            version: BASELINE_VERSION,
+            findings,
+        }
+    }
+
+    /// Load a baseline, returning `None` if the file does not exist yet.
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::error::Result<Option<Self>> {
+        let path = path.as_ref();
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let baseline: Baseline = serde_json::from_str(&text).map_err(|e| {
+            HealthwandError::ConfigError(format!("{}: invalid baseline: {}", path.display(), e))
+        })?;
+        if baseline.version != BASELINE_VERSION {
+            return Err(HealthwandError::ConfigError(format!(
+                "{}: unsupported baseline version {} (expected {})",
+                path.display(),
+                baseline.version,
+                BASELINE_VERSION
+            )));
+        }
+        Ok(Some(baseline))
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> crate::error::Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| HealthwandError::ConfigError(e.to_string()))?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    /// Start matching findings against this baseline.
+    pub fn matcher(&self) -> BaselineMatcher<'_> {
+        let mut remaining = HashMap::new();
+        for entry in &self.findings {
+            *remaining.entry(entry).or_insert(0usize) += 1;
+        }
+        BaselineMatcher {
+            baseline: self,
+            remaining,
+        }
+    }
+}
+
+/// Consumes baseline entries as findings are matched against them.
+pub struct BaselineMatcher<'a> {
+    baseline: &'a Baseline,
+    remaining: HashMap<&'a BaselineEntry, usize>,
+}
+
+impl BaselineMatcher<'_> {
+    /// Whether `entry` is covered by the baseline. Each baseline entry covers one finding.
+    pub fn take(&mut self, entry: &BaselineEntry) -> bool {
+        match self.remaining.get_mut(entry) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The baseline without entries that were never matched, i.e. findings since fixed.
+    pub fn pruned(&self) -> Baseline {
+        let mut unmatched = self.remaining.clone();
+        let findings = self
+            .baseline
+            .findings
+            .iter()
+            .filter(|entry| match unmatched.get_mut(entry) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            })
+            .cloned()
+            .collect();
+        Baseline::new(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(path: &str, fp: &str) -> BaselineEntry {
+        BaselineEntry {
+            file_path: path.to_string(),
+            pattern_id: "ssn".to_string(),
+            fingerprint: fp.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_matcher_counts_duplicates() {
+        let baseline = Baseline::new(vec![entry("a.txt", "f1"), entry("a.txt", "f1")]);
+        let mut matcher = baseline.matcher();
+        assert!(matcher.take(&entry("a.txt", "f1")));
+        assert!(matcher.take(&entry("a.txt", "f1")));
+        assert!(!matcher.take(&entry("a.txt", "f1")));
+        assert!(!matcher.take(&entry("b.txt", "f1")));
+    }
+
+    #[test]
+    fn test_prune_drops_fixed_findings() {
+        let baseline = Baseline::new(vec![
+            entry("a.txt", "f1"),
+            entry("a.txt", "f2"),
+            entry("b.txt", "f3"),
+        ]);
+        let mut matcher = baseline.matcher();
+        assert!(matcher.take(&entry("a.txt", "f2")));
+        let pruned = matcher.pruned();
+        assert_eq!(pruned.findings, vec![entry("a.txt", "f2")]);
+    }
+
+    #[test]
+    fn test_load_missing_and_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        assert!(Baseline::load(&path).unwrap().is_none());
+        let baseline = Baseline::new(vec![entry("a.txt", "f1")]);
+        baseline.write(&path).unwrap();
+        assert_eq!(Baseline::load(&path).unwrap(), Some(baseline));
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_version() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        std::fs::write(&path, r#"{"version": 9, "findings": []}"#).unwrap();
+        match Baseline::load(&path).unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("version 9")),
+            _ => panic!("Expected ConfigError"),
+        }
+    }
+}
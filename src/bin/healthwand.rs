@@ -1,42 +1,258 @@
-use clap::{Parser, ValueEnum};
-use healthwand::file_source::{FileSource, LocalFileSource};
-use healthwand::phi_patterns;
+use clap::{Parser, Subcommand};
+use healthwand::allowlist::Allowlist;
+use healthwand::baseline::{Baseline, BaselineEntry};
+use healthwand::config::{self, PartialSettings, ResolvedCatalogue, ResolvedSettings};
+use healthwand::encoding::EncodingRules;
+use healthwand::file_source::{
+    ArchiveFileSource, Entry, FileSource, GitDiffSource, GitHistorySource, GitScope,
+    LocalFileSource, MemoryFileSource,
+};
+use healthwand::fingerprint;
+use healthwand::format::{Formatter, OutputFormat, SarifFormatter};
+use healthwand::phi_patterns::PHIPatternsFile;
 use healthwand::redactor::*;
-use healthwand::results::{DetectionResult, OutputBundle, ResultsSummary};
+use healthwand::results::{DetectionResult, OutputBundle, ResultsSummary, phi_type};
+use healthwand::rules::RuleSet;
 use healthwand::scanner;
+use healthwand::{RedactionStrategy, Severity};
+use std::io::{Read, Write};
+use std::path::PathBuf;
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::filter::LevelFilter;
 
-/// Supported output formats
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
-enum OutputFormat {
-    Json,
-    Text,
-}
-
 /// CLI configuration
 #[derive(Parser, Debug)]
 #[command(name = "healthwand")]
 #[command(about = "Detect and redact PHI in text files", long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
-    /// Input file or directory to scan
-    #[arg(short, long)]
-    input: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Named settings profile (built in: ci, strict, research, redact-export)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Input file or directory to scan, or - to read stdin
+    #[arg(short, long, required_unless_present_any = ["catalogue_print", "list_detectors", "text", "git"])]
+    input: Option<String>,
+
+    /// Scan this text instead of an input
+    #[arg(long, conflicts_with = "input")]
+    text: Option<String>,
+
+    /// Only report findings on lines added by staged changes (in the --input repository, default .)
+    #[arg(long, group = "git", conflicts_with = "text")]
+    git_staged: bool,
+
+    /// Only report findings on lines added between two commits
+    #[arg(long, group = "git", conflicts_with = "text", value_name = "BASE..HEAD", value_parser = str::parse::<GitScope>)]
+    git_diff: Option<GitScope>,
 
-    /// Output format (json or text)
-    #[arg(short, long, value_enum, default_value_t = OutputFormat::Json)]
-    output: OutputFormat,
+    /// Only report findings on lines added by uncommitted changes and untracked files
+    #[arg(long, group = "git", conflicts_with = "text")]
+    git_worktree: bool,
+
+    /// Scan every file version in the repository's history, once per blob
+    #[arg(long, group = "git", conflicts_with = "text")]
+    git_history: bool,
+
+    /// With --git-history, write the IDs of blobs with findings to this file (for git filter-repo --strip-blobs-with-ids)
+    #[arg(long, requires = "git_history")]
+    purge_list: Option<PathBuf>,
+
+    /// Print the effective pattern catalogue (YAML, or JSON with --output json) and exit
+    #[arg(long, default_value_t = false)]
+    catalogue_print: bool,
+
+    /// List each detector's ID, name, type, severity, category and UU PDP article and exit
+    #[arg(long, default_value_t = false)]
+    list_detectors: bool,
+
+    /// Output format (json, text or sarif) [default: json]
+    #[arg(short, long, value_enum)]
+    output: Option<OutputFormat>,
 
     /// Enable redaction (replace PHI with placeholders)
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, overrides_with = "no_redact")]
     redact: bool,
 
+    /// Disable redaction, even if a settings file or the environment enables it
+    #[arg(long, default_value_t = false, overrides_with = "redact")]
+    no_redact: bool,
+
+    /// Redaction strategy (full, partial or placeholder) [default: full]
+    #[arg(long)]
+    redaction_strategy: Option<RedactionStrategy>,
+
+    /// Only scan files whose path matches this glob (repeatable)
+    #[arg(long)]
+    include: Vec<String>,
+
+    /// Skip files whose path matches this glob (repeatable)
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Also scan hidden files and directories
+    #[arg(long, default_value_t = false, overrides_with = "no_hidden")]
+    hidden: bool,
+
+    /// Skip hidden files and directories, even if a settings file or the environment includes them
+    #[arg(long, default_value_t = false, overrides_with = "hidden")]
+    no_hidden: bool,
+
+    /// Follow symlinks, skipping loops and links that leave the input directory
+    #[arg(long, default_value_t = false, overrides_with = "no_follow_symlinks")]
+    follow_symlinks: bool,
+
+    /// Do not follow symlinks, even if a settings file or the environment enables it
+    #[arg(long, default_value_t = false, overrides_with = "follow_symlinks")]
+    no_follow_symlinks: bool,
+
+    /// With --follow-symlinks, also follow links that leave the input directory
+    #[arg(
+        long,
+        default_value_t = false,
+        overrides_with = "no_symlinks_outside_root"
+    )]
+    symlinks_outside_root: bool,
+
+    /// Do not follow links that leave the input directory, even if a settings file or the environment allows it
+    #[arg(
+        long,
+        default_value_t = false,
+        overrides_with = "symlinks_outside_root"
+    )]
+    no_symlinks_outside_root: bool,
+
+    /// Also scan files inside zip, tar and gzip archives
+    #[arg(long, default_value_t = false, overrides_with = "no_archives")]
+    archives: bool,
+
+    /// Do not scan inside archives, even if a settings file or the environment enables it
+    #[arg(long, default_value_t = false, overrides_with = "archives")]
+    no_archives: bool,
+
+    /// Skip archives nested deeper than this inside other archives [default: 3]
+    #[arg(long)]
+    archive_max_depth: Option<usize>,
+
+    /// Stop reading an archive after this many decompressed bytes, e.g. 1GB; 0 for
+    /// no limit [default: 512MB]
+    #[arg(long)]
+    archive_max_size: Option<String>,
+
+    /// Only scan files with this extension (repeatable) instead of sniffing content
+    #[arg(long)]
+    extension: Vec<String>,
+
+    /// Skip files larger than this, e.g. 10MB; 0 for no limit [default: 50MB]
+    #[arg(long)]
+    max_file_size: Option<String>,
+
+    /// Pattern catalogue (YAML) replacing the bundled config/phi_patterns.yaml
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Extra catalogue merged on top of the others (repeatable, applied in order)
+    #[arg(long, global = true)]
+    config_include: Vec<PathBuf>,
+
+    /// Allowlist file of known synthetic values and fixture paths
+    #[arg(long)]
+    allowlist: Option<PathBuf>,
+
+    /// Baseline file: created from the current findings if missing, otherwise only
+    /// findings not in it are reported
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Rewrite the baseline without entries for findings that have since been fixed
+    #[arg(long, requires = "baseline", default_value_t = false)]
+    prune_baseline: bool,
+
+    /// Exit with code 1 if an active finding is at or above this severity [default:
+    /// exit 0 whatever is found]
+    #[arg(long)]
+    min_severity: Option<Severity>,
+
+    /// Drop findings whose confidence score is below this (0.0 to 1.0) [default: 0.0]
+    #[arg(long)]
+    min_score: Option<f32>,
+
+    /// Context-word window (characters) for every pattern, replacing the catalogue's
+    #[arg(long)]
+    context_window: Option<usize>,
+
+    /// Maximum compiled size of a catalogue regex, in bytes [default: 1048576]
+    #[arg(long, global = true)]
+    regex_size_limit: Option<usize>,
+
+    /// Maximum lazy DFA cache of a catalogue regex, in bytes [default: 2097152]
+    #[arg(long, global = true)]
+    regex_dfa_size_limit: Option<usize>,
+
+    /// Print the time each pattern spent matching to stderr after the scan
+    #[arg(long, default_value_t = false)]
+    profile_patterns: bool,
+
+    /// Reject `healthwand:ignore` directives that carry no justification
+    #[arg(
+        long,
+        default_value_t = false,
+        overrides_with = "no_strict_suppressions"
+    )]
+    strict_suppressions: bool,
+
+    /// Accept unjustified `healthwand:ignore` directives, even if a settings file or the environment rejects them
+    #[arg(long, default_value_t = false, overrides_with = "strict_suppressions")]
+    no_strict_suppressions: bool,
+
     /// Verbosity level (repeat for more verbose)
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Validate a catalogue and self-test its examples without scanning
+    Validate {
+        /// Catalogue file to validate; defaults to the layered catalogue
+        file: Option<PathBuf>,
+    },
+    /// Inspect the pattern catalogue
+    Catalogue {
+        #[command(subcommand)]
+        action: CatalogueCommand,
+    },
+    /// Inspect run settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print each effective setting and where it came from
+    Show,
+}
+
+#[derive(Subcommand, Debug)]
+enum CatalogueCommand {
+    /// Print the merged catalogue and the layer each field came from
+    Resolve,
+    /// Upgrade a catalogue file to the current schema_version
+    Migrate {
+        /// Catalogue file to upgrade
+        file: PathBuf,
+        /// Rewrite the file instead of printing the upgraded catalogue
+        #[arg(long, default_value_t = false)]
+        in_place: bool,
+    },
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("File IO error: {0}")]
@@ -60,93 +276,354 @@ fn main() {
     };
     tracing_subscriber::fmt()
         .with_max_level(level_filter)
+        .with_writer(std::io::stderr)
         .init();
-    println!("Parsed CLI args: {:?}", cli);
+    debug!("Parsed CLI args: {:?}", cli);
+
+    if let Some(Command::Catalogue {
+        action: CatalogueCommand::Migrate { file, in_place },
+    }) = &cli.command
+    {
+        std::process::exit(migrate_catalogue(file, *in_place));
+    }
+
+    let repo_root = repo_root(&cli);
+    let resolved_settings = match load_settings(&cli, &repo_root) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            error!("Invalid settings: {}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(Command::Config {
+        action: ConfigCommand::Show,
+    }) = &cli.command
+    {
+        print_settings(&resolved_settings, &repo_root);
+        return;
+    }
+    // Piped input with --redact prints the redacted text unless a report format was chosen.
+    let print_redacted = (cli.text.is_some() || cli.input.as_deref() == Some("-"))
+        && resolved_settings.settings.redact
+        && resolved_settings.source("output") == &config::SettingSource::Default;
+    let settings = resolved_settings.settings;
+
+    let layers = match &cli.command {
+        Some(Command::Validate { file: Some(file) }) => {
+            vec![config::CatalogueLayer::new(config::LayerKind::Cli, file)]
+        }
+        _ => config::discover_layers(
+            settings.catalogue.as_deref(),
+            &repo_root,
+            &settings.catalogue_include,
+        ),
+    };
+    for layer in &layers {
+        info!("Catalogue layer: {}", layer.label());
+    }
+    let resolved = match config::load_layers(&layers, &settings.regex_limits) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            error!("Invalid pattern catalogue: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let input = match cli.command {
+        Some(Command::Catalogue {
+            action: CatalogueCommand::Resolve,
+        }) => {
+            print_resolved(&resolved);
+            return;
+        }
+        Some(Command::Validate { .. }) => {
+            std::process::exit(print_self_test(&resolved));
+        }
+        Some(Command::Catalogue {
+            action: CatalogueCommand::Migrate { .. },
+        })
+        | Some(Command::Config { .. }) => unreachable!("handled before loading the catalogue"),
+        None if cli.list_detectors => {
+            print_detectors(&resolved.catalogue.detectors(), settings.output);
+            return;
+        }
+        None if cli.catalogue_print => {
+            std::process::exit(print_catalogue(&resolved, settings.output));
+        }
+        None => cli.input.clone().unwrap_or_else(|| ".".to_string()),
+    };
+
     info!("Starting PHI detection pipeline");
     let mut summary = ResultsSummary::default();
     let mut all_results = Vec::new();
     let mut errors = Vec::new();
 
-    // Allowed text file extensions
-    let allowed_exts = ["txt", "md", "csv"]; // Extend as needed
-    let file_source = LocalFileSource::new(
-        &cli.input,
-        allowed_exts.iter().map(|s| s.to_string()).collect(),
-    );
+    let mut patterns = resolved.catalogue.into_patterns();
+    if let Some(window) = settings.context_window {
+        for pattern in &mut patterns {
+            pattern.context_window = window;
+        }
+    }
+    let allowlist = match settings
+        .allowlist
+        .as_deref()
+        .map(|path| Allowlist::from_yaml_file(path, &settings.regex_limits))
+    {
+        Some(Ok(allowlist)) => allowlist.with_glob_root(&repo_root),
+        Some(Err(e)) => {
+            error!("Invalid allowlist: {}", e);
+            std::process::exit(2);
+        }
+        None => Allowlist::default(),
+    };
+    let baseline = match settings.baseline.as_deref().map(Baseline::load) {
+        Some(Ok(baseline)) => baseline,
+        Some(Err(e)) => {
+            error!("Invalid baseline: {}", e);
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    let mut baseline_matcher = baseline.as_ref().map(Baseline::matcher);
+    let mut new_baseline = Vec::new();
+    let mut gate_failed = false;
+    let fingerprint_key = match &settings.fingerprint_key {
+        Some(key) => key.0.as_bytes().to_vec(),
+        None => {
+            // Only SARIF and baselines carry fingerprints without the matched text.
+            if settings.output == OutputFormat::Sarif || settings.baseline.is_some() {
+                warn!(
+                    "No fingerprint_key set; using the built-in key, so fingerprints of short identifiers can be brute-forced. Set HEALTHWAND_FINGERPRINT_KEY or fingerprint_key in the user config."
+                );
+            }
+            fingerprint::DEFAULT_KEY.to_vec()
+        }
+    };
+    let scanner = scanner::Scanner::new(patterns, 10)
+        .with_strict_suppressions(settings.strict_suppressions)
+        .with_min_score(settings.min_score)
+        .with_pattern_timings(cli.profile_patterns)
+        .with_rules(match RuleSet::new(&settings.rules) {
+            Ok(rules) => rules.with_glob_root(&repo_root),
+            Err(e) => {
+                error!("Invalid rules: {}", e);
+                std::process::exit(2);
+            }
+        })
+        .with_fingerprint_key(fingerprint_key);
 
-    match file_source.files() {
-        Ok(files) => {
-            if files.is_empty() {
-                println!("No text files found in the specified input.");
-            } else {
-                println!("Found {} file(s):", files.len());
-                for f in &files {
-                    println!("  {}", f.display());
+    let file_source: Box<dyn FileSource> = if let Some(text) = &cli.text {
+        Box::new(MemoryFileSource::new().with_file("<text>", text.as_str()))
+    } else if input == "-" {
+        let mut bytes = Vec::new();
+        if let Err(e) = std::io::stdin().lock().read_to_end(&mut bytes) {
+            error!("Failed to read stdin: {}", e);
+            std::process::exit(3);
+        }
+        Box::new(MemoryFileSource::new().with_file("<stdin>", bytes))
+    } else {
+        path_source(&input, &repo_root, &settings, git_mode(&cli))
+    };
+    let mut redacted_output = Vec::new();
+    let mut purge_blobs = std::collections::BTreeSet::new();
+
+    let mut files_found = 0;
+    for entry in file_source.entries() {
+        let f = match entry {
+            Ok(Entry::File(f)) => f,
+            Ok(Entry::Skipped(skip)) => {
+                debug!("Skipped {}: {}", skip.id, skip.reason);
+                *summary.skipped_files.entry(skip.reason).or_insert(0) += 1;
+                continue;
+            }
+            Err(e) => {
+                error!("Error traversing input: {}", e);
+                errors.push(format!("Traverse: {}", e));
+                summary.errors.push(format!("Traverse: {}", e));
+                continue;
+            }
+        };
+        files_found += 1;
+        debug!("Scanning {}", f);
+        match file_source.read_decoded(&f) {
+            Ok(decoded) => {
+                // A file version from history is reported at its path in the tree.
+                let (path, origin) = match file_source.origin(&f) {
+                    Some((path, origin)) => (path, Some(origin)),
+                    None => (f.as_path(), None),
+                };
+                let content = &decoded.text;
+                let mut detections = scanner.scan_path(path, content);
+                if let Some(lines) = file_source.reported_lines(&f) {
+                    detections.retain(|d| lines.iter().any(|range| range.contains(&d.span.line)));
                 }
-                for f in &files {
-                    match file_source.read_file(f) {
-                        Ok(content) => {
-                            let scanner =
-                                scanner::Scanner::new(phi_patterns::PHIPattern::all_patterns(), 10);
-                            let detections = scanner.scan(&content);
-                            // Only perform redaction if requested
-                            let mut redacted_map = std::collections::HashMap::new();
-                            let _redacted = if cli.redact {
-                                let redactor = Redactor::new(RedactionStrategy::FullReplacement);
-                                // Precompute redacted text for each detection
-                                for det in &detections {
-                                    let replacement =
-                                        redactor.redaction_text(&det.phi_type, &det.matched_text);
-                                    redacted_map.insert((det.start, det.end), replacement);
-                                }
-                                redactor.redact(&content, &detections)
-                            } else {
-                                content.clone()
-                            };
-
-                            for det in &detections {
-                                let result = DetectionResult {
-                                    file_path: f.display().to_string(),
-                                    phi_type: det.phi_type.clone(),
-                                    location: (det.start, det.end),
-                                    context: det.context.clone(),
-                                    matched_text: det.matched_text.clone(),
-                                    redacted_text: if cli.redact {
-                                        redacted_map.get(&(det.start, det.end)).cloned()
-                                    } else {
-                                        None
-                                    },
-                                };
-                                *summary
-                                    .detections_by_type
-                                    .entry(det.phi_type.clone())
-                                    .or_insert(0) += 1;
-                                all_results.push(result);
-                            }
-                            summary.files_processed += 1;
-                            summary.total_detections += detections.len();
-                            if cli.redact {
-                                summary.redacted_count += detections.len();
+                let before = detections.len();
+                detections.retain(|d| match allowlist.matching(path, d) {
+                    Some(allowed) => {
+                        debug!(
+                            "{} at {}:{} allowlisted by entry {} ({})",
+                            d.pattern_id,
+                            path.display(),
+                            d.span.line,
+                            allowed.entry,
+                            allowed.reason.unwrap_or("no reason given")
+                        );
+                        false
+                    }
+                    None => true,
+                });
+                summary.allowlisted_count += before - detections.len();
+                // Suppressed findings are reported but left in place
+                let active: Vec<_> = detections
+                    .iter()
+                    .filter(|d| !d.is_suppressed())
+                    .cloned()
+                    .collect();
+                // Only perform redaction if requested
+                let mut redacted_map = std::collections::HashMap::new();
+                if settings.redact {
+                    let redactor = Redactor::new(settings.redaction_strategy);
+                    // Precompute redacted text for each detection
+                    for det in &active {
+                        let replacement = redactor.redaction_text(det, &det.matched_text);
+                        redacted_map.insert((det.span.start, det.span.end), replacement);
+                    }
+                    if print_redacted {
+                        redacted_output.push(redactor.redact_encoded(&decoded, &active));
+                    }
+                }
+
+                for det in &detections {
+                    if let Some(s) = det.suppression.as_ref().filter(|s| s.rejected) {
+                        summary.errors.push(format!(
+                            "{}:{}: suppression without justification rejected (strict mode)",
+                            path.display(),
+                            s.directive_line
+                        ));
+                    }
+                    if settings.baseline.is_some() && !det.is_suppressed() {
+                        let entry = BaselineEntry {
+                            file_path: path.display().to_string(),
+                            pattern_id: det.pattern_id.to_string(),
+                            fingerprint: det.fingerprint.clone().unwrap_or_default(),
+                        };
+                        let known = match baseline_matcher.as_mut() {
+                            Some(matcher) => matcher.take(&entry),
+                            None => {
+                                new_baseline.push(entry);
+                                true
                             }
+                        };
+                        if known {
+                            summary.baselined_count += 1;
+                            continue;
+                        }
+                    }
+                    let result = DetectionResult {
+                        file_path: path.display().to_string(),
+                        phi_type: phi_type(&det.pattern_id),
+                        pattern_id: det.pattern_id.clone(),
+                        severity: det.severity,
+                        location: {
+                            let span = decoded.original_span(det.span);
+                            (span.start, span.end)
+                        },
+                        line: Some(det.span.line),
+                        context: det.context.clone().unwrap_or_default(),
+                        matched_text: det.matched_text.clone(),
+                        redacted_text: if settings.redact {
+                            redacted_map.get(&(det.span.start, det.span.end)).cloned()
+                        } else {
+                            None
+                        },
+                        suppression: det.suppression.clone(),
+                        fingerprint: det.fingerprint.clone(),
+                        rules: det.rules.clone(),
+                        origin: origin.cloned(),
+                    };
+                    if det.is_suppressed() {
+                        summary.suppressed_count += 1;
+                    } else {
+                        if settings.min_severity.is_some_and(|min| det.severity >= min) {
+                            gate_failed = true;
                         }
-                        Err(e) => {
-                            error!("Error reading {}: {}", f.display(), e);
-                            errors.push(format!("Read: {}", e));
-                            summary.errors.push(format!("Read: {}", e));
+                        if let Some(origin) = origin {
+                            purge_blobs.insert(origin.blob.clone());
                         }
                     }
+                    *summary
+                        .detections_by_type
+                        .entry(phi_type(&det.pattern_id))
+                        .or_insert(0) += 1;
+                    summary.total_detections += 1;
+                    all_results.push(result);
                 }
+                summary.files_processed += 1;
+                if settings.redact {
+                    summary.redacted_count += active.len();
+                }
+            }
+            Err(e) => {
+                error!("Error reading {}: {}", f, e);
+                errors.push(format!("Read: {}", e));
+                summary.errors.push(format!("Read: {}", e));
             }
         }
-        Err(e) => {
-            println!("Error traversing input: {}", e);
-            errors.push(format!("Traverse: {}", e));
-            summary.errors.push(format!("Traverse: {}", e));
+    }
+    if files_found == 0 && errors.is_empty() {
+        warn!("No text files found in the specified input.");
+    } else {
+        info!("Scanned {} file(s)", files_found);
+    }
+
+    if let Some(path) = settings.baseline.as_deref() {
+        let updated = match &baseline_matcher {
+            Some(matcher) if cli.prune_baseline => Some(matcher.pruned()),
+            Some(_) => None,
+            None => Some(Baseline::new(new_baseline)),
+        };
+        if let Some(updated) = updated {
+            if let Err(e) = updated.write(path) {
+                error!("Failed to write baseline {}: {}", path.display(), e);
+                std::process::exit(3);
+            }
+            info!(
+                "Wrote baseline {} with {} finding(s)",
+                path.display(),
+                updated.findings.len()
+            );
+        }
+    }
+
+    if let Some(path) = &cli.purge_list {
+        let list: String = purge_blobs
+            .iter()
+            .map(|blob| format!("{}\n", blob))
+            .collect();
+        if let Err(e) = std::fs::write(path, list) {
+            error!("Failed to write purge list {}: {}", path.display(), e);
+            std::process::exit(3);
         }
+        info!(
+            "Wrote {} blob(s) to purge to {}",
+            purge_blobs.len(),
+            path.display()
+        );
     }
 
-    // Output results according to --output format
-    match cli.output {
+    // Output results according to the output setting
+    match settings.output {
+        _ if print_redacted => {
+            let mut stdout = std::io::stdout().lock();
+            if let Err(e) = redacted_output
+                .iter()
+                .try_for_each(|bytes| stdout.write_all(bytes))
+                .and_then(|()| stdout.flush())
+            {
+                error!("Failed to write redacted text: {}", e);
+                std::process::exit(3);
+            }
+        }
         OutputFormat::Json => {
             let output_bundle = OutputBundle {
                 results: all_results,
@@ -160,13 +637,30 @@ fn main() {
                 }
             }
         }
+        OutputFormat::Sarif => {
+            let output_bundle = OutputBundle {
+                results: all_results,
+                summary,
+            };
+            if let Err(e) = SarifFormatter.format(&output_bundle, &mut std::io::stdout()) {
+                error!("Failed to write SARIF: {}", e);
+                errors.push(format!("Serialize: {}", e));
+            }
+        }
         OutputFormat::Text => {
             println!("Detection Results:");
             for result in &all_results {
+                let suppressed = match &result.suppression {
+                    Some(s) if !s.rejected => " [suppressed]",
+                    _ => "",
+                };
                 println!(
-                    "- File: {} | Type: {:?} | Location: {:?} | Context: {} | Matched: {} | Redacted: {}",
+                    "- File: {} | Type: {} | Severity: {}{} | Line: {} | Location: {:?} | Context: {} | Matched: {} | Redacted: {}",
                     result.file_path,
-                    result.phi_type,
+                    result.pattern_id,
+                    result.severity,
+                    suppressed,
+                    result.line.unwrap_or_default(),
                     result.location,
                     result.context,
                     result.matched_text,
@@ -174,21 +668,397 @@ fn main() {
                 );
             }
             println!(
-                "\nSummary:\n  Files processed: {}\n  Total detections: {}\n  Redacted: {}\n  Detections by type: {:?}",
+                "\nSummary:\n  Files processed: {}\n  Total detections: {}\n  Redacted: {}\n  Suppressed: {}\n  Allowlisted: {}\n  Baselined: {}\n  Detections by type: {:?}",
                 summary.files_processed,
                 summary.total_detections,
                 summary.redacted_count,
+                summary.suppressed_count,
+                summary.allowlisted_count,
+                summary.baselined_count,
                 summary.detections_by_type
             );
+            if !summary.skipped_files.is_empty() {
+                let skipped: Vec<String> = summary
+                    .skipped_files
+                    .iter()
+                    .map(|(reason, n)| format!("{} {}", n, reason))
+                    .collect();
+                println!("  Skipped: {}", skipped.join(", "));
+            }
             if !summary.errors.is_empty() {
                 println!("  Errors: {:?}", summary.errors);
             }
         }
     }
 
+    if cli.profile_patterns {
+        print_pattern_timings(&scanner.pattern_timings());
+    }
+
     // Print errors if any
     if !errors.is_empty() {
-        println!("Errors: {:?}", errors);
+        eprintln!("Errors: {:?}", errors);
+    }
+    if gate_failed {
+        std::process::exit(1);
+    }
+}
+
+/// What to read from the input's git repository instead of the files on disk.
+#[derive(Debug, PartialEq)]
+enum GitMode {
+    /// The lines a change adds (`--git-staged`, `--git-diff`, `--git-worktree`).
+    Diff(GitScope),
+    /// Every blob in the history (`--git-history`).
+    History,
+}
+
+fn git_mode(cli: &Cli) -> Option<GitMode> {
+    if cli.git_staged {
+        Some(GitMode::Diff(GitScope::Staged))
+    } else if cli.git_worktree {
+        Some(GitMode::Diff(GitScope::Worktree))
+    } else if cli.git_history {
+        Some(GitMode::History)
+    } else {
+        cli.git_diff.clone().map(GitMode::Diff)
+    }
+}
+
+/// The source over a file or directory input, as configured by `settings`, or over
+/// its git repository's changes or history. Globs are relative to `repo_root`.
+fn path_source(
+    input: &str,
+    repo_root: &std::path::Path,
+    settings: &config::Settings,
+    git_mode: Option<GitMode>,
+) -> Box<dyn FileSource> {
+    let local_source = LocalFileSource::new(input, settings.extensions.clone())
+        .with_include(&settings.include)
+        .and_then(|source| source.with_exclude(&settings.exclude))
+        .unwrap_or_else(|e| {
+            // Already checked when the settings were resolved.
+            error!("Invalid path glob: {}", e);
+            std::process::exit(2);
+        })
+        .with_glob_root(repo_root)
+        .with_hidden(settings.hidden)
+        .with_follow_symlinks(settings.follow_symlinks)
+        .with_symlinks_outside_root(settings.symlinks_outside_root)
+        .with_max_file_size(settings.max_file_size)
+        .with_extractors(settings.extractors.clone())
+        .with_encodings(match EncodingRules::new(&settings.encodings) {
+            Ok(encodings) => encodings,
+            Err(e) => {
+                error!("Invalid encodings: {}", e);
+                std::process::exit(2);
+            }
+        });
+    let source = match git_mode {
+        Some(GitMode::Diff(scope)) => GitDiffSource::new(local_source, scope)
+            .map(|source| Box::new(source) as Box<dyn FileSource>),
+        Some(GitMode::History) => GitHistorySource::new(local_source)
+            .map(|source| Box::new(source) as Box<dyn FileSource>),
+        None if settings.archives => Ok(Box::new(ArchiveFileSource::new(
+            local_source,
+            settings.archive_limits,
+        )) as Box<dyn FileSource>),
+        None => Ok(Box::new(local_source) as Box<dyn FileSource>),
+    };
+    source.unwrap_or_else(|e| {
+        error!("Failed to read the git repository: {}", e);
+        std::process::exit(3);
+    })
+}
+
+/// Where to look for repository settings and catalogues: the nearest directory at
+/// or above the input that holds a `.git`, else the input's own directory. Text and
+/// stdin input use the working directory.
+fn repo_root(cli: &Cli) -> PathBuf {
+    let dir = match cli.input.as_deref().filter(|i| *i != "-") {
+        Some(input) if std::path::Path::new(input).is_dir() => PathBuf::from(input),
+        Some(input) => std::path::Path::new(input)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), std::path::Path::to_path_buf),
+        None => PathBuf::from("."),
+    };
+    // Absolute, so `--input docs` run from the repository root still finds it.
+    std::path::absolute(&dir)
+        .unwrap_or_else(|_| dir.clone())
+        .ancestors()
+        .find(|a| a.join(".git").exists())
+        .map_or(dir, std::path::Path::to_path_buf)
+}
+
+/// Resolve run settings: defaults < user config < `.healthwand.yaml` < profile < env < CLI.
+fn load_settings(
+    cli: &Cli,
+    repo_root: &std::path::Path,
+) -> healthwand::error::Result<ResolvedSettings> {
+    let mut layers = config::settings::file_layers(repo_root)?;
+    layers.push(config::SettingsLayer {
+        source: config::SettingSource::Env,
+        values: PartialSettings::from_env(|name| std::env::var(name).ok())?,
+    });
+    layers.push(config::SettingsLayer {
+        source: config::SettingSource::Cli,
+        values: cli_settings(cli),
+    });
+    for layer in &layers {
+        debug!("Settings layer: {:?}", layer);
+    }
+    config::resolve(&layers)
+}
+
+/// The settings given as flags; flags left at their defaults are unset.
+fn cli_settings(cli: &Cli) -> PartialSettings {
+    // `--x` and `--no-x` override each other, so at most one is set.
+    let switch = |on: bool, off: bool| match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    };
+    PartialSettings {
+        profile: cli.profile.clone(),
+        profiles: None,
+        catalogue: cli.config.clone(),
+        catalogue_include: (!cli.config_include.is_empty()).then(|| cli.config_include.clone()),
+        include: (!cli.include.is_empty()).then(|| cli.include.clone()),
+        exclude: (!cli.exclude.is_empty()).then(|| cli.exclude.clone()),
+        hidden: switch(cli.hidden, cli.no_hidden),
+        follow_symlinks: switch(cli.follow_symlinks, cli.no_follow_symlinks),
+        symlinks_outside_root: switch(cli.symlinks_outside_root, cli.no_symlinks_outside_root),
+        extensions: (!cli.extension.is_empty()).then(|| cli.extension.clone()),
+        extractors: None,
+        encodings: None,
+        archives: switch(cli.archives, cli.no_archives),
+        archive_max_depth: cli.archive_max_depth,
+        archive_max_size: cli.archive_max_size.clone().map(config::ByteSize::Text),
+        max_file_size: cli.max_file_size.clone().map(config::ByteSize::Text),
+        rules: None,
+        min_severity: cli.min_severity.map(|s| s.to_string()),
+        min_score: cli.min_score,
+        context_window: cli.context_window,
+        output: cli.output.map(|o| o.to_string()),
+        redact: switch(cli.redact, cli.no_redact),
+        redaction_strategy: cli.redaction_strategy.map(|r| r.label().to_string()),
+        regex_size_limit: cli.regex_size_limit,
+        regex_dfa_size_limit: cli.regex_dfa_size_limit,
+        allowlist: cli.allowlist.clone(),
+        baseline: cli.baseline.clone(),
+        strict_suppressions: switch(cli.strict_suppressions, cli.no_strict_suppressions),
+        fingerprint_key: None,
+    }
+}
+
+/// `config show`: the config files read, then one line per setting with its source.
+fn print_settings(resolved: &ResolvedSettings, repo_root: &std::path::Path) {
+    let user = config::settings::user_config_path();
+    let repo = repo_root.join(config::settings::REPOSITORY_CONFIG);
+    for (label, path) in [("user config", user), ("repository config", Some(repo))] {
+        match path {
+            Some(path) if path.is_file() => println!("# {}: {}", label, path.display()),
+            Some(path) => println!("# {}: {} (not found)", label, path.display()),
+            None => println!("# {}: (no home directory)", label),
+        }
+    }
+    let rows = resolved.explain();
+    let name_width = rows.iter().map(|(n, ..)| n.len()).max().unwrap_or(0);
+    let value_width = rows.iter().map(|(_, v, _)| v.len()).max().unwrap_or(0);
+    for (name, value, source) in rows {
+        println!(
+            "{:name_width$}  {:value_width$}  # {}",
+            name,
+            value,
+            source,
+            name_width = name_width,
+            value_width = value_width
+        );
+    }
+}
+
+/// `--profile-patterns`: slowest patterns first, on stderr so reports stay parseable.
+fn print_pattern_timings(timings: &[scanner::PatternTiming]) {
+    let width = timings
+        .iter()
+        .map(|t| t.pattern_id.as_str().len())
+        .max()
+        .unwrap_or(0)
+        .max("PATTERN".len());
+    eprintln!(
+        "{:width$}  {:>12}  {:>8}  {:>8}",
+        "PATTERN",
+        "TIME",
+        "SCANS",
+        "MATCHES",
+        width = width
+    );
+    for t in timings {
+        eprintln!(
+            "{:width$}  {:>12}  {:>8}  {:>8}",
+            t.pattern_id.as_str(),
+            format!("{:.3?}", t.total),
+            t.scans,
+            t.matches,
+            width = width
+        );
+    }
+}
+
+/// `--list-detectors`: a table for text output, JSON otherwise.
+fn print_detectors(detectors: &[config::DetectorInfo], output: OutputFormat) {
+    if output != OutputFormat::Text {
+        match serde_json::to_string_pretty(detectors) {
+            Ok(json) => println!("{}", json),
+            Err(e) => error!("Failed to serialize detectors: {}", e),
+        }
+        return;
+    }
+    let header = ["ID", "NAME", "TYPE", "SEVERITY", "CATEGORY", "UU PDP"];
+    let rows: Vec<[String; 6]> = detectors
+        .iter()
+        .map(|d| {
+            [
+                d.id.clone(),
+                d.name.clone(),
+                d.detector_type.to_string(),
+                d.severity.to_string(),
+                d.category.clone(),
+                d.uu_pdp_article
+                    .map_or_else(|| "-".to_string(), |a| a.to_string()),
+            ]
+        })
+        .collect();
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let print_row = |cells: &[&str]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+    print_row(&header);
+    for row in &rows {
+        print_row(&row.each_ref().map(String::as_str));
+    }
+}
+
+/// `--catalogue-print`: the merged catalogue as YAML, or JSON with `--output json`.
+fn print_catalogue(resolved: &ResolvedCatalogue, output: OutputFormat) -> i32 {
+    let catalogue = PHIPatternsFile {
+        schema_version: Some(config::CURRENT_SCHEMA_VERSION),
+        patterns: resolved.entries.clone(),
+        defaults: Default::default(),
+        overrides: Default::default(),
+    };
+    let printed = if output == OutputFormat::Text {
+        serde_yaml_ng::to_string(&catalogue).map_err(|e| e.to_string())
+    } else {
+        serde_json::to_string_pretty(&catalogue).map_err(|e| e.to_string())
+    };
+    match printed {
+        Ok(text) => {
+            println!("{}", text.trim_end());
+            0
+        }
+        Err(e) => {
+            error!("Failed to serialize catalogue: {}", e);
+            3
+        }
+    }
+}
+
+/// `validate`: print example self-test results and return the process exit code.
+fn print_self_test(resolved: &ResolvedCatalogue) -> i32 {
+    let report = config::self_test(&resolved.entries, &resolved.catalogue);
+    for warning in &resolved.warnings {
+        println!("warn {}", warning);
+    }
+    for failure in &report.failures {
+        let expectation = if failure.negative {
+            "negative example matched"
+        } else {
+            "example did not match"
+        };
+        println!(
+            "FAIL {}: {} {:?}",
+            failure.pattern_id, expectation, failure.example
+        );
+    }
+    for collision in &report.collisions {
+        println!(
+            "warn {}: example {:?} also matched by {}",
+            collision.pattern_id,
+            collision.example,
+            collision.also_matched_by.join(", ")
+        );
+    }
+    println!(
+        "{} pattern(s), {} example(s) checked: {} failure(s), {} collision(s)",
+        resolved.entries.len(),
+        report.examples_checked,
+        report.failures.len(),
+        report.collisions.len()
+    );
+    if report.passed() { 0 } else { 1 }
+}
+
+/// `catalogue migrate`: returns the process exit code.
+fn migrate_catalogue(file: &std::path::Path, in_place: bool) -> i32 {
+    let migration = match std::fs::read_to_string(file)
+        .map_err(healthwand::error::HealthwandError::from)
+        .and_then(|yaml| config::migrate(&yaml))
+    {
+        Ok(migration) => migration,
+        Err(e) => {
+            error!("Cannot migrate {}: {}", file.display(), e);
+            return 2;
+        }
+    };
+    for change in &migration.changes {
+        eprintln!("{}: {}", file.display(), change);
+    }
+    if !in_place {
+        print!("{}", migration.yaml);
+    } else if migration.changes.is_empty() {
+        eprintln!("{}: already at the current schema", file.display());
+    } else if let Err(e) = std::fs::write(file, &migration.yaml) {
+        error!("Failed to write {}: {}", file.display(), e);
+        return 3;
+    }
+    0
+}
+
+/// `catalogue resolve`: one block per pattern, one line per field with its source.
+fn print_resolved(resolved: &ResolvedCatalogue) {
+    let width = resolved
+        .fields
+        .iter()
+        .map(|f| f.field.len())
+        .max()
+        .unwrap_or(0);
+    for pattern in resolved.catalogue.patterns() {
+        println!("{}", pattern.id);
+        for field in resolved
+            .fields
+            .iter()
+            .filter(|f| f.pattern_id == pattern.id.as_str())
+        {
+            println!(
+                "  {:width$}  {}  # {}",
+                field.field,
+                field.value,
+                field.source,
+                width = width
+            );
+        }
     }
 }
 
@@ -209,9 +1079,164 @@ mod tests {
             "-vv",
         ];
         let cli = Cli::parse_from(args);
-        assert_eq!(cli.input, "data/file.txt");
-        assert_eq!(cli.output, OutputFormat::Text);
+        assert_eq!(cli.input.as_deref(), Some("data/file.txt"));
+        assert!(cli.command.is_none());
+        assert_eq!(cli.output, Some(OutputFormat::Text));
         assert!(cli.redact);
+        assert!(!cli.strict_suppressions);
+        assert_eq!(cli.min_severity, None);
+        assert_eq!(cli.redaction_strategy, None);
+        assert!(cli.exclude.is_empty());
+        assert!(cli.config.is_none());
+        assert!(cli.config_include.is_empty());
+        assert!(cli.allowlist.is_none());
+        assert!(cli.baseline.is_none());
+        assert!(!cli.prune_baseline);
+        assert!(!cli.catalogue_print && !cli.list_detectors);
         assert_eq!(cli.verbose, 2);
     }
+
+    #[test]
+    fn test_catalogue_resolve_needs_no_input() {
+        let cli = Cli::parse_from([
+            "healthwand",
+            "catalogue",
+            "resolve",
+            "--config-include",
+            "a.yaml",
+            "--config-include",
+            "b.yaml",
+        ]);
+        assert!(cli.input.is_none());
+        assert!(matches!(
+            cli.command,
+            Some(Command::Catalogue {
+                action: CatalogueCommand::Resolve
+            })
+        ));
+        assert_eq!(
+            cli.config_include,
+            vec![PathBuf::from("a.yaml"), PathBuf::from("b.yaml")]
+        );
+        assert!(Cli::try_parse_from(["healthwand"]).is_err());
+        assert!(Cli::try_parse_from(["healthwand", "--list-detectors"]).is_ok());
+
+        let cli = Cli::parse_from(["healthwand", "validate", "my-patterns.yaml"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Validate { file: Some(ref f) }) if f == &PathBuf::from("my-patterns.yaml")
+        ));
+    }
+
+    #[test]
+    fn test_stdin_and_text_input() {
+        let cli = Cli::parse_from(["healthwand", "--input", "-", "--redact"]);
+        assert_eq!(cli.input.as_deref(), Some("-"));
+        let cli = Cli::parse_from(["healthwand", "--text", "NIK 3171234567890123"]);
+        assert!(cli.input.is_none());
+        assert_eq!(cli.text.as_deref(), Some("NIK 3171234567890123"));
+        assert!(Cli::try_parse_from(["healthwand", "--text", "x", "--input", "data"]).is_err());
+    }
+
+    #[test]
+    fn test_git_modes() {
+        let cli = Cli::parse_from(["healthwand", "--git-diff", "origin/main...HEAD"]);
+        assert!(cli.input.is_none());
+        assert_eq!(
+            git_mode(&cli),
+            Some(GitMode::Diff(GitScope::Range {
+                base: "origin/main".to_string(),
+                head: "HEAD".to_string(),
+                symmetric: true
+            }))
+        );
+        let cli = Cli::parse_from(["healthwand", "--git-staged", "--input", "services/api"]);
+        assert_eq!(git_mode(&cli), Some(GitMode::Diff(GitScope::Staged)));
+        let cli = Cli::parse_from(["healthwand", "--git-history", "--purge-list", "purge.txt"]);
+        assert_eq!(git_mode(&cli), Some(GitMode::History));
+        assert_eq!(cli.purge_list, Some(PathBuf::from("purge.txt")));
+        assert!(Cli::try_parse_from(["healthwand", "-i", ".", "--purge-list", "p"]).is_err());
+        assert!(Cli::try_parse_from(["healthwand", "--git-staged", "--git-worktree"]).is_err());
+        assert!(Cli::try_parse_from(["healthwand", "--git-diff", "main"]).is_err());
+        assert!(Cli::try_parse_from(["healthwand", "--git-worktree", "--text", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_settings_only_set_given_flags() {
+        let cli = Cli::parse_from([
+            "healthwand",
+            "--input",
+            "data",
+            "--min-severity",
+            "high",
+            "--redaction-strategy",
+            "partial",
+            "--exclude",
+            "**/fixtures/**",
+        ]);
+        let settings = cli_settings(&cli);
+        assert_eq!(settings.min_severity.as_deref(), Some("high"));
+        assert_eq!(settings.redaction_strategy.as_deref(), Some("partial"));
+        assert_eq!(settings.exclude, Some(vec!["**/fixtures/**".to_string()]));
+        assert_eq!(settings.output, None);
+        assert_eq!(settings.redact, None);
+        assert_eq!(settings.catalogue_include, None);
+
+        let cli = Cli::parse_from(["healthwand", "config", "show", "--config", "p.yaml"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Config {
+                action: ConfigCommand::Show
+            })
+        ));
+        assert_eq!(cli_settings(&cli).catalogue, Some(PathBuf::from("p.yaml")));
+    }
+
+    #[test]
+    fn test_no_flags_turn_settings_off() {
+        let cli = Cli::parse_from([
+            "healthwand",
+            "--input",
+            "data",
+            "--no-redact",
+            "--no-hidden",
+            "--archives",
+            "--no-archives",
+            "--no-strict-suppressions",
+            "--strict-suppressions",
+        ]);
+        let settings = cli_settings(&cli);
+        assert_eq!(settings.redact, Some(false));
+        assert_eq!(settings.hidden, Some(false));
+        // The later of `--x` and `--no-x` wins.
+        assert_eq!(settings.archives, Some(false));
+        assert_eq!(settings.strict_suppressions, Some(true));
+        assert_eq!(settings.follow_symlinks, None);
+    }
+
+    #[test]
+    fn test_repo_root_follows_input() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        std::fs::write(repo.join("src/notes.txt"), "").unwrap();
+        let plain = dir.path().join("plain");
+        std::fs::create_dir_all(&plain).unwrap();
+
+        let root = |input: &std::path::Path| {
+            repo_root(&Cli::parse_from([
+                "healthwand",
+                "--input",
+                input.to_str().unwrap(),
+            ]))
+        };
+        assert_eq!(root(&repo.join("src")), repo);
+        assert_eq!(root(&repo.join("src/notes.txt")), repo);
+        assert_eq!(root(&plain), plain);
+        assert_eq!(
+            repo_root(&Cli::parse_from(["healthwand", "--text", "x"])),
+            repo_root(&Cli::parse_from(["healthwand", "--input", "."]))
+        );
+    }
 }
@@ -0,0 +1,561 @@
+//! Layered catalogues (ARCHITECTURE §5.3).
+//!
+//! Catalogues are merged in order: built-in (or `--config`, which replaces it),
+//! organization, repository, then each `--config-include`. A pattern whose `id` was
+//! already defined replaces the earlier one, or patches it field by field when the
+//! entry sets `merge: patch`; new IDs are appended. `defaults` and `overrides` are
+//! merged key by key, later layers winning.
+//!
+//! Merging happens on raw YAML so that patch entries can leave out required fields,
+//! and so that every field can be traced back to the layer that set it.
+
+use crate::config::validate::CompiledRegexes;
+use crate::config::{PatternCatalogue, build_catalogue, in_source, parse_value};
+use crate::domain::RegexLimits;
+use crate::error::HealthwandError;
+use crate::phi_patterns::{PHIPatternConfig, PHIPatternsFile};
+use serde_yaml_ng::{Mapping, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Repository catalogue, relative to the repository root.
+pub const REPOSITORY_CATALOGUE: &str = ".healthwand/patterns.yaml";
+
+/// Where a catalogue layer sits in the merge order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LayerKind {
+    BuiltIn,
+    Organization,
+    Repository,
+    Cli,
+}
+
+impl fmt::Display for LayerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayerKind::BuiltIn => write!(f, "built-in"),
+            LayerKind::Organization => write!(f, "organization"),
+            LayerKind::Repository => write!(f, "repository"),
+            LayerKind::Cli => write!(f, "cli"),
+        }
+    }
+}
+
+/// One catalogue in the merge order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogueLayer {
+    pub kind: LayerKind,
+    /// `None` for the catalogue embedded in the binary.
+    pub path: Option<PathBuf>,
+}
+
+impl CatalogueLayer {
+    pub fn new(kind: LayerKind, path: impl Into<PathBuf>) -> Self {
+        Self {
+            kind,
+            path: Some(path.into()),
+        }
+    }
+
+    /// The embedded default catalogue.
+    pub fn built_in() -> Self {
+        Self {
+            kind: LayerKind::BuiltIn,
+            path: None,
+        }
+    }
+
+    /// e.g. `repository (.healthwand/patterns.yaml)`, or `built-in` for the embedded catalogue.
+    pub fn label(&self) -> String {
+        match &self.path {
+            Some(path) => format!("{} ({})", self.kind, path.display()),
+            None => self.kind.to_string(),
+        }
+    }
+
+    fn source(&self) -> String {
+        match &self.path {
+            Some(path) => path.display().to_string(),
+            None => super::BUILT_IN_SOURCE.to_string(),
+        }
+    }
+
+    fn read(&self) -> crate::error::Result<Cow<'static, [u8]>> {
+        match &self.path {
+            Some(path) => Ok(Cow::Owned(std::fs::read(path)?)),
+            None => Ok(Cow::Borrowed(super::DEFAULT_CATALOGUE)),
+        }
+    }
+}
+
+/// Find the catalogue layers for a scan.
+///
+/// `config` replaces the built-in catalogue; organization and repository catalogues
+/// are used when they exist; `includes` are appended in order.
+pub fn discover_layers(
+    config: Option<&Path>,
+    repo_root: &Path,
+    includes: &[PathBuf],
+) -> Vec<CatalogueLayer> {
+    let mut layers = vec![match config {
+        Some(path) => CatalogueLayer::new(LayerKind::Cli, path),
+        None => CatalogueLayer::built_in(),
+    }];
+    if let Some(org) = organization_catalogue().filter(|p| p.is_file()) {
+        layers.push(CatalogueLayer::new(LayerKind::Organization, org));
+    }
+    let repo = repo_root.join(REPOSITORY_CATALOGUE);
+    if repo.is_file() {
+        layers.push(CatalogueLayer::new(LayerKind::Repository, repo));
+    }
+    layers.extend(
+        includes
+            .iter()
+            .map(|p| CatalogueLayer::new(LayerKind::Cli, p)),
+    );
+    layers
+}
+
+/// `$XDG_CONFIG_HOME/healthwand/patterns.yaml`, falling back to `~/.config`.
+fn organization_catalogue() -> Option<PathBuf> {
+    super::user_config_dir().map(|dir| dir.join("patterns.yaml"))
+}
+
+/// The effective value of one pattern field and the layer it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedField {
+    pub pattern_id: String,
+    /// Dotted path, e.g. `metadata.severity`.
+    pub field: String,
+    pub value: String,
+    /// Layer label, prefixed with `defaults` or `overrides` when the value came from those sections.
+    pub source: String,
+}
+
+/// A merged catalogue with per-field provenance.
+#[derive(Debug, Clone)]
+pub struct ResolvedCatalogue {
+    pub catalogue: PatternCatalogue,
+    /// The merged entries the catalogue was built from, e.g. for example self-tests.
+    pub entries: Vec<PHIPatternConfig>,
+    pub fields: Vec<ResolvedField>,
+    /// Validation warnings, one `file:line:column: ...` line each.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Default)]
+struct Merger {
+    patterns: Vec<(String, Mapping)>,
+    defaults: Mapping,
+    overrides: Mapping,
+    pattern_src: HashMap<(String, String), String>,
+    defaults_src: HashMap<String, String>,
+    override_src: HashMap<(String, String), String>,
+}
+
+/// Merge `layers` in order and build the effective catalogue, compiling regexes
+/// within `limits`.
+pub fn load_layers(
+    layers: &[CatalogueLayer],
+    limits: &RegexLimits,
+) -> crate::error::Result<ResolvedCatalogue> {
+    let mut merger = Merger::default();
+    let mut regexes = CompiledRegexes::new(*limits);
+    let mut warnings = Vec::new();
+    for layer in layers {
+        let source = layer.source();
+        let (doc, layer_warnings) = parse_value(&layer.read()?, &source, &mut regexes)?;
+        warnings.extend(layer_warnings.iter().map(|w| format!("{}:{}", source, w)));
+        merger
+            .add(doc, &layer.label())
+            .map_err(|e| in_source(&source, e))?;
+    }
+    let mut resolved = merger.finish(&mut regexes)?;
+    resolved.warnings = warnings;
+    Ok(resolved)
+}
+
+impl Merger {
+    fn add(&mut self, doc: Value, label: &str) -> crate::error::Result<()> {
+        let Value::Mapping(mut doc) = doc else {
+            return Err(HealthwandError::ConfigError(
+                "catalogue must be a mapping".to_string(),
+            ));
+        };
+
+        doc.remove("schema_version");
+        if let Some(patterns) = doc.remove("patterns") {
+            let Value::Sequence(patterns) = patterns else {
+                return Err(HealthwandError::ConfigError(
+                    "patterns must be a list".to_string(),
+                ));
+            };
+            for entry in patterns {
+                self.add_pattern(entry, label)?;
+            }
+        }
+        if let Some(Value::Mapping(defaults)) = doc.remove("defaults") {
+            for path in leaves(&Value::Mapping(defaults.clone())) {
+                self.defaults_src.insert(path.0, label.to_string());
+            }
+            deep_merge(&mut self.defaults, defaults);
+        }
+        if let Some(Value::Mapping(overrides)) = doc.remove("overrides") {
+            for (id, patch) in overrides {
+                let id_str = id.as_str().unwrap_or_default().to_string();
+                for (path, _) in leaves(&patch) {
+                    self.override_src
+                        .insert((id_str.clone(), path), label.to_string());
+                }
+                match (self.overrides.get_mut(&id), patch) {
+                    (Some(Value::Mapping(existing)), Value::Mapping(patch)) => {
+                        deep_merge(existing, patch)
+                    }
+                    (_, patch) => {
+                        self.overrides.insert(id, patch);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_pattern(&mut self, entry: Value, label: &str) -> crate::error::Result<()> {
+        let Value::Mapping(mut entry) = entry else {
+            return Err(HealthwandError::ConfigError(
+                "pattern entries must be mappings".to_string(),
+            ));
+        };
+        let id = entry
+            .get("id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| HealthwandError::ConfigError("pattern without an id".to_string()))?
+            .to_string();
+        let patch = match entry.remove("merge") {
+            None => false,
+            Some(Value::String(mode)) if mode == "replace" => false,
+            Some(Value::String(mode)) if mode == "patch" => true,
+            Some(other) => {
+                return Err(HealthwandError::ConfigError(format!(
+                    "pattern '{}': merge must be \"replace\" or \"patch\", got {:?}",
+                    id, other
+                )));
+            }
+        };
+
+        let existing = self.patterns.iter_mut().find(|(pid, _)| *pid == id);
+        match existing {
+            Some((_, current)) if patch => deep_merge(current, entry.clone()),
+            Some((_, current)) => {
+                self.pattern_src.retain(|(pid, _), _| *pid != id);
+                *current = entry.clone();
+            }
+            None if patch => {
+                return Err(HealthwandError::ConfigError(format!(
+                    "pattern '{}': merge: patch, but no earlier layer defines it",
+                    id
+                )));
+            }
+            None => self.patterns.push((id.clone(), entry.clone())),
+        }
+        for (path, _) in leaves(&Value::Mapping(entry)) {
+            self.pattern_src
+                .insert((id.clone(), path), label.to_string());
+        }
+        Ok(())
+    }
+
+    fn finish(self, regexes: &mut CompiledRegexes) -> crate::error::Result<ResolvedCatalogue> {
+        let mut doc = Mapping::new();
+        doc.insert(
+            "patterns".into(),
+            Value::Sequence(
+                self.patterns
+                    .iter()
+                    .map(|(_, p)| Value::Mapping(p.clone()))
+                    .collect(),
+            ),
+        );
+        doc.insert("defaults".into(), Value::Mapping(self.defaults.clone()));
+        doc.insert("overrides".into(), Value::Mapping(self.overrides.clone()));
+        let file: PHIPatternsFile = serde_yaml_ng::from_value(Value::Mapping(doc))
+            .map_err(|e| HealthwandError::YamlError(e.to_string()))?;
+
+        let resolved = file.resolve()?;
+        let fields = resolved
+            .iter()
+            .flat_map(|pattern| self.provenance(pattern))
+            .collect();
+        Ok(ResolvedCatalogue {
+            catalogue: build_catalogue(resolved.clone(), regexes)?,
+            entries: resolved,
+            fields,
+            warnings: Vec::new(),
+        })
+    }
+
+    fn provenance(&self, pattern: &PHIPatternConfig) -> Vec<ResolvedField> {
+        let value = serde_yaml_ng::to_value(pattern).unwrap_or(Value::Null);
+        leaves(&value)
+            .into_iter()
+            .filter(|(field, _)| field != "id")
+            .map(|(field, value)| {
+                let key = (pattern.id.clone(), field.clone());
+                let source = if let Some(src) = self
+                    .override_src
+                    .get(&(pattern.id.clone(), override_key(&field).to_string()))
+                {
+                    format!("overrides in {}", src)
+                } else if let Some(src) = self.pattern_src.get(&key) {
+                    src.clone()
+                } else if let Some(src) = self.defaults_src.get(defaults_key(&field)) {
+                    format!("defaults in {}", src)
+                } else {
+                    "-".to_string()
+                };
+                ResolvedField {
+                    pattern_id: pattern.id.clone(),
+                    field,
+                    value: display(&value),
+                    source,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Key under `overrides.<id>` that sets the pattern field at `path`.
+fn override_key(path: &str) -> &str {
+    match path {
+        "metadata.severity" => "severity",
+        "metadata.category" => "category",
+        other => other,
+    }
+}
+
+/// Key under `defaults` that fills the pattern field at `path`.
+fn defaults_key(path: &str) -> &str {
+    match path {
+        "metadata.severity" => "severity",
+        other => other,
+    }
+}
+
+/// Recursively merge `patch` into `base`; mappings merge, everything else is replaced.
+fn deep_merge(base: &mut Mapping, patch: Mapping) {
+    for (key, value) in patch {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Mapping(existing)), Value::Mapping(value)) => deep_merge(existing, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Flatten mappings into `(dotted.path, value)` leaves. Lists are leaves; nulls are skipped.
+fn leaves(value: &Value) -> Vec<(String, Value)> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+        match value {
+            Value::Mapping(map) => {
+                for (key, value) in map {
+                    let key = key
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| display(key));
+                    let path = if prefix.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(&path, value, out);
+                }
+            }
+            Value::Null => {}
+            other => out.push((prefix.to_string(), other.clone())),
+        }
+    }
+    let mut out = Vec::new();
+    walk("", value, &mut out);
+    out
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        // Confidences are f32; print them as written rather than widened to f64.
+        Value::Number(n) if n.is_f64() => (n.as_f64().unwrap_or_default() as f32).to_string(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Severity;
+    use tempfile::TempDir;
+
+    const BASE: &str = r#"
+patterns:
+  - id: "ssn"
+    name: "Social Security Number"
+    regex: "\\b\\d{3}-\\d{2}-\\d{4}\\b"
+    metadata: { severity: "high", category: "identifier" }
+  - id: "indonesian_bpjs"
+    name: "Indonesian BPJS"
+    regex: "\\b\\d{13}\\b"
+defaults:
+  confidence: 0.8
+  severity: "medium"
+"#;
+
+    fn layer(dir: &TempDir, name: &str, kind: LayerKind, yaml: &str) -> CatalogueLayer {
+        let path = dir.path().join(name);
+        std::fs::write(&path, yaml).unwrap();
+        CatalogueLayer::new(kind, path)
+    }
+
+    fn source_of<'a>(resolved: &'a ResolvedCatalogue, id: &str, field: &str) -> &'a str {
+        &resolved
+            .fields
+            .iter()
+            .find(|f| f.pattern_id == id && f.field == field)
+            .unwrap()
+            .source
+    }
+
+    #[test]
+    fn test_replace_patch_and_append() {
+        let dir = TempDir::new().unwrap();
+        let layers = vec![
+            layer(&dir, "base.yaml", LayerKind::BuiltIn, BASE),
+            layer(
+                &dir,
+                "repo.yaml",
+                LayerKind::Repository,
+                r#"
+patterns:
+  - id: "ssn"
+    merge: patch
+    metadata: { severity: "critical" }
+  - id: "indonesian_bpjs"
+    name: "BPJS (hospital format)"
+    regex: "\\bBPJS-\\d{13}\\b"
+  - id: "hospital-mrn"
+    name: "Hospital MRN"
+    regex: "\\bRM-\\d{6}\\b"
+"#,
+            ),
+        ];
+        let resolved = load_layers(&layers, &RegexLimits::default()).unwrap();
+        let catalogue = &resolved.catalogue;
+        assert_eq!(catalogue.patterns().len(), 3);
+
+        let ssn = catalogue.get("ssn").unwrap();
+        assert_eq!(ssn.default_severity, Severity::Critical);
+        assert_eq!(ssn.name, "Social Security Number");
+
+        let bpjs = catalogue.get("indonesian_bpjs").unwrap();
+        assert!(bpjs.regex.as_ref().unwrap().is_match("BPJS-1234567890123"));
+        assert_eq!(bpjs.default_severity, Severity::Medium);
+        assert_eq!(catalogue.patterns()[2].id.as_str(), "hospital-mrn");
+
+        assert!(source_of(&resolved, "ssn", "name").starts_with("built-in"));
+        assert!(source_of(&resolved, "ssn", "metadata.severity").starts_with("repository"));
+        assert!(
+            source_of(&resolved, "indonesian_bpjs", "metadata.severity")
+                .starts_with("defaults in built-in")
+        );
+    }
+
+    #[test]
+    fn test_later_overrides_and_defaults_win() {
+        let dir = TempDir::new().unwrap();
+        let layers = vec![
+            layer(&dir, "base.yaml", LayerKind::BuiltIn, BASE),
+            layer(
+                &dir,
+                "org.yaml",
+                LayerKind::Organization,
+                "defaults:\n  confidence: 0.9\noverrides:\n  indonesian_bpjs: { severity: \"high\" }\n",
+            ),
+            layer(
+                &dir,
+                "cli.yaml",
+                LayerKind::Cli,
+                "overrides:\n  indonesian_bpjs: { severity: \"critical\" }\n",
+            ),
+        ];
+        let resolved = load_layers(&layers, &RegexLimits::default()).unwrap();
+        let bpjs = resolved.catalogue.get("indonesian_bpjs").unwrap();
+        assert_eq!(bpjs.default_severity, Severity::Critical);
+        assert_eq!(bpjs.score.value(), 0.9);
+        assert!(
+            source_of(&resolved, "indonesian_bpjs", "metadata.severity")
+                .starts_with("overrides in cli")
+        );
+        assert!(
+            source_of(&resolved, "indonesian_bpjs", "confidence")
+                .starts_with("defaults in organization")
+        );
+    }
+
+    #[test]
+    fn test_patch_of_unknown_pattern_rejected() {
+        let dir = TempDir::new().unwrap();
+        let layers = vec![
+            layer(&dir, "base.yaml", LayerKind::BuiltIn, BASE),
+            layer(
+                &dir,
+                "extra.yaml",
+                LayerKind::Cli,
+                "patterns:\n  - id: \"nik\"\n    merge: patch\n    confidence: 0.5\n",
+            ),
+        ];
+        match load_layers(&layers, &RegexLimits::default()).unwrap_err() {
+            HealthwandError::ConfigError(msg) => {
+                assert!(msg.contains("extra.yaml") && msg.contains("'nik'"))
+            }
+            _ => panic!("Expected ConfigError"),
+        }
+    }
+
+    #[test]
+    fn test_discover_layers_order() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(".healthwand")).unwrap();
+        std::fs::write(dir.path().join(REPOSITORY_CATALOGUE), "patterns: []\n").unwrap();
+        let include = PathBuf::from("extra.yaml");
+        let layers = discover_layers(None, dir.path(), std::slice::from_ref(&include));
+        assert_eq!(layers[0].kind, LayerKind::BuiltIn);
+        let kinds: Vec<_> = layers.iter().map(|l| l.kind).collect();
+        assert!(kinds.ends_with(&[LayerKind::Repository, LayerKind::Cli]));
+        assert_eq!(layers.last().unwrap().path, Some(include));
+    }
+
+    #[test]
+    fn test_regex_over_size_limit_names_pattern() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("greedy.yaml");
+        std::fs::write(
+            &path,
+            "patterns:\n  - id: \"greedy\"\n    name: \"Greedy\"\n    regex: \"\\\\w{20}\"\n",
+        )
+        .unwrap();
+        let layers = [CatalogueLayer::new(LayerKind::Cli, &path)];
+        let limits = RegexLimits {
+            size_limit: 10_000,
+            ..RegexLimits::default()
+        };
+        match load_layers(&layers, &limits).unwrap_err() {
+            HealthwandError::ConfigError(msg) => {
+                assert!(msg.contains("greedy.yaml:4:12: pattern 'greedy': regex over the limits"));
+                assert!(msg.contains("10000"));
+            }
+            other => panic!("Expected ConfigError, got {}", other),
+        }
+        assert!(load_layers(&layers, &RegexLimits::default()).is_ok());
+    }
+}
@@ -0,0 +1,316 @@
+//! Pattern catalogue loading.
+//!
+//! The YAML catalogue (`config/phi_patterns.yaml`) is the single source of truth for
+//! patterns. Every entry is converted into a domain [`Pattern`] through
+//! [`Pattern::validated`], so no invalid pattern reaches the scanner.
+
+use crate::domain::{DetectorType, Pattern, RegexLimits, Severity, UuPdpArticle};
+use crate::error::HealthwandError;
+use crate::phi_patterns::{PHIPatternConfig, PHIPatternsFile};
+use std::path::{Path, PathBuf};
+use validate::CompiledRegexes;
+
+pub mod layers;
+pub mod profiles;
+pub mod selftest;
+pub mod settings;
+pub mod validate;
+pub mod version;
+
+pub use layers::{
+    CatalogueLayer, LayerKind, ResolvedCatalogue, ResolvedField, discover_layers, load_layers,
+};
+pub use profiles::ProfileDefinition;
+pub use selftest::{Collision, ExampleFailure, SelfTestReport, self_test};
+pub use settings::{
+    ByteSize, PartialSettings, ResolvedSettings, Secret, SettingSource, Settings, SettingsLayer,
+    resolve,
+};
+pub use validate::{Diagnostic, Validation, validate_document};
+pub use version::{CURRENT_SCHEMA_VERSION, Migration, SchemaVersion, migrate};
+
+/// The default catalogue (`config/phi_patterns.yaml`), compiled into the crate.
+pub const DEFAULT_CATALOGUE: &[u8] = include_bytes!("../../config/phi_patterns.yaml");
+
+/// How the embedded catalogue is named in errors and warnings.
+const BUILT_IN_SOURCE: &str = "<built-in catalogue>";
+
+/// A validated set of patterns, ready for the scanner.
+#[derive(Debug, Clone, Default)]
+pub struct PatternCatalogue {
+    patterns: Vec<Pattern>,
+}
+
+impl PatternCatalogue {
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    pub fn into_patterns(self) -> Vec<Pattern> {
+        self.patterns
+    }
+
+    /// Look up a pattern by catalogue ID.
+    pub fn get(&self, id: &str) -> Option<&Pattern> {
+        self.patterns.iter().find(|p| p.id.as_str() == id)
+    }
+
+    /// One summary row per pattern, for `--list-detectors`.
+    pub fn detectors(&self) -> Vec<DetectorInfo> {
+        self.patterns
+            .iter()
+            .map(|p| DetectorInfo {
+                id: p.id.to_string(),
+                name: p.name.clone(),
+                detector_type: p.detector_type,
+                severity: p.default_severity,
+                category: p.category.to_string(),
+                uu_pdp_article: p.uu_pdp_article,
+            })
+            .collect()
+    }
+}
+
+/// Summary of one pattern: what it detects and how it is classified.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DetectorInfo {
+    pub id: String,
+    pub name: String,
+    pub detector_type: DetectorType,
+    pub severity: Severity,
+    pub category: String,
+    pub uu_pdp_article: Option<UuPdpArticle>,
+}
+
+/// Parse a catalogue from YAML bytes, applying its `defaults` and `overrides`.
+pub fn load_yaml(bytes: &[u8]) -> crate::error::Result<PatternCatalogue> {
+    parse_catalogue(bytes, "<catalogue>")
+}
+
+/// Load the embedded default catalogue.
+pub fn load_default() -> crate::error::Result<PatternCatalogue> {
+    parse_catalogue(DEFAULT_CATALOGUE, BUILT_IN_SOURCE)
+}
+
+/// Load a catalogue file, naming the file in any error.
+pub fn load_file<P: AsRef<Path>>(path: P) -> crate::error::Result<PatternCatalogue> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    parse_catalogue(&bytes, &path.display().to_string())
+}
+
+fn parse_catalogue(bytes: &[u8], source: &str) -> crate::error::Result<PatternCatalogue> {
+    let mut regexes = CompiledRegexes::new(RegexLimits::default());
+    let file = parse_document(bytes, source, &mut regexes)?;
+    build_catalogue(
+        file.resolve().map_err(|e| in_source(source, e))?,
+        &mut regexes,
+    )
+    .map_err(|e| in_source(source, e))
+}
+
+/// Parse and validate one catalogue file without resolving it.
+///
+/// `schema_version` is checked first, so a file from another major version fails
+/// with a version error rather than a confusing field error. Schema and semantic
+/// problems are all reported together, each with its line and column.
+pub(crate) fn parse_document(
+    bytes: &[u8],
+    source: &str,
+    regexes: &mut CompiledRegexes,
+) -> crate::error::Result<PHIPatternsFile> {
+    let (doc, _warnings) = parse_value(bytes, source, regexes)?;
+    serde_yaml_ng::from_value(doc)
+        .map_err(|e| HealthwandError::YamlError(format!("{}: {}", source, e)))
+}
+
+/// Parse YAML, check its version and validate it, returning the raw document and
+/// any warnings.
+pub(crate) fn parse_value(
+    bytes: &[u8],
+    source: &str,
+    regexes: &mut CompiledRegexes,
+) -> crate::error::Result<(serde_yaml_ng::Value, Vec<Diagnostic>)> {
+    let text = std::str::from_utf8(bytes)
+        .map_err(|e| HealthwandError::YamlError(format!("{}: {}", source, e)))?;
+    let doc: serde_yaml_ng::Value = serde_yaml_ng::from_str(text)
+        .map_err(|e| HealthwandError::YamlError(format!("{}: {}", source, e)))?;
+    version::check_version(version::declared_version(&doc)?, source)
+        .map_err(|e| in_source(source, e))?;
+    let validation = validate::validate(text, &doc, regexes);
+    if !validation.errors.is_empty() {
+        return Err(validate::to_error(source, &validation.errors));
+    }
+    Ok((doc, validation.warnings))
+}
+
+/// Convert resolved catalogue entries into validated patterns, reusing regexes
+/// compiled during validation.
+pub(crate) fn build_catalogue(
+    resolved: Vec<PHIPatternConfig>,
+    regexes: &mut CompiledRegexes,
+) -> crate::error::Result<PatternCatalogue> {
+    let patterns = resolved
+        .into_iter()
+        .map(|p| p.into_pattern_compiled(regexes))
+        .collect::<crate::error::Result<Vec<_>>>()?;
+    Ok(PatternCatalogue { patterns })
+}
+
+/// `$XDG_CONFIG_HOME/healthwand`, falling back to `~/.config/healthwand`.
+pub(crate) fn user_config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("healthwand"))
+}
+
+/// Prefix an error with the catalogue file it came from.
+pub(crate) fn in_source(source: &str, e: HealthwandError) -> HealthwandError {
+    match e {
+        HealthwandError::YamlError(msg) => {
+            HealthwandError::YamlError(format!("{}: {}", source, msg))
+        }
+        HealthwandError::ConfigError(msg) => {
+            HealthwandError::ConfigError(format!("{}: {}", source, msg))
+        }
+        // Names the pattern, which is what a reader needs to find it.
+        e @ HealthwandError::RegexError { .. } => e,
+        other => HealthwandError::ConfigError(format!("{}: {}", source, other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Category, RedactionStrategy, Severity};
+
+    fn default_catalogue() -> PatternCatalogue {
+        load_default().unwrap()
+    }
+
+    fn regex_of<'a>(catalogue: &'a PatternCatalogue, id: &str) -> &'a regex::Regex {
+        catalogue.get(id).unwrap().regex.as_ref().unwrap()
+    }
+
+    #[test]
+    fn test_ssn_pattern() {
+        let catalogue = default_catalogue();
+        let re = regex_of(&catalogue, "ssn");
+        assert!(re.is_match("123-45-6789"));
+        assert!(!re.is_match("123456789"));
+    }
+
+    #[test]
+    fn test_medical_record_pattern() {
+        let catalogue = default_catalogue();
+        let re = regex_of(&catalogue, "mrn-generic");
+        assert!(re.is_match("12345678"));
+        assert!(re.is_match("123456789012"));
+        assert!(!re.is_match("1234"));
+        assert!(!re.is_match("abc12345678"));
+        assert!(regex_of(&catalogue, "mrn").is_match("MRN: 123456789"));
+    }
+
+    #[test]
+    fn test_icd10_pattern() {
+        let catalogue = default_catalogue();
+        let re = regex_of(&catalogue, "icd10");
+        assert!(re.is_match("A12"));
+        assert!(re.is_match("B99.8"));
+        assert!(re.is_match("C01.123"));
+        assert!(!re.is_match("123-45-6789"));
+    }
+
+    #[test]
+    fn test_date_of_birth_pattern() {
+        let catalogue = default_catalogue();
+        let re = regex_of(&catalogue, "dob");
+        assert!(re.is_match("12/31/2000"));
+        assert!(re.is_match("2000-12-31"));
+        assert!(re.is_match("31/12/2000"));
+        assert!(!re.is_match("20001231"));
+    }
+
+    #[test]
+    fn test_indonesian_nik_pattern() {
+        let catalogue = default_catalogue();
+        let re = regex_of(&catalogue, "indonesian_nik");
+        assert!(re.is_match("1234567890123456"));
+        assert!(!re.is_match("123456789012345"));
+        assert!(!re.is_match("12345678901234567"));
+        assert!(!re.is_match("1234-5678-9012-3456"));
+    }
+
+    #[test]
+    fn test_indonesian_bpjs_pattern() {
+        let catalogue = default_catalogue();
+        let re = regex_of(&catalogue, "indonesian_bpjs");
+        assert!(re.is_match("1234567890123"));
+        assert!(!re.is_match("123456789012"));
+        assert!(!re.is_match("12345678901234"));
+        assert!(!re.is_match("BPJS1234567890"));
+    }
+
+    #[test]
+    fn test_yaml_fields_map_to_domain_types() {
+        let catalogue = default_catalogue();
+        let mrn = catalogue.get("mrn").unwrap();
+        assert_eq!(mrn.score.value(), 0.90);
+        assert_eq!(mrn.default_severity, Severity::Medium);
+        assert_eq!(mrn.category, Category::Medical);
+        assert_eq!(
+            mrn.redaction_strategy,
+            Some(RedactionStrategy::PartialMasking)
+        );
+        assert_eq!(mrn.context_window, 24);
+        assert_eq!(
+            mrn.uu_pdp_article,
+            Some(crate::domain::UuPdpArticle::Art4_1)
+        );
+        assert!(catalogue.get("icd10").unwrap().escalation.is_some());
+    }
+
+    #[test]
+    fn test_embedded_catalogue_lists_every_detector() {
+        let detectors = default_catalogue().detectors();
+        assert_eq!(detectors.len(), 7);
+        let nik = detectors.iter().find(|d| d.id == "indonesian_nik").unwrap();
+        assert_eq!(nik.detector_type, DetectorType::Regex);
+        assert_eq!(nik.severity, Severity::High);
+        assert_eq!(nik.uu_pdp_article, Some(UuPdpArticle::Art4_2));
+        let json = serde_json::to_value(nik).unwrap();
+        assert_eq!(json["uu_pdp_article"], "4(2)");
+        assert_eq!(json["detector_type"], "regex");
+    }
+
+    #[test]
+    fn test_invalid_entry_names_pattern() {
+        let yaml = br#"
+patterns:
+  - id: "broken"
+    name: "Broken"
+    regex: "(unclosed"
+    redaction:
+      template: "[X]"
+      strategy: "full"
+"#;
+        match load_yaml(yaml).unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("'broken'")),
+            _ => panic!("Expected ConfigError"),
+        }
+    }
+
+    #[test]
+    fn test_major_version_mismatch_names_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("pack.yaml");
+        std::fs::write(&path, "schema_version: \"2.0\"\npatterns: []\n").unwrap();
+        match load_file(&path).unwrap_err() {
+            HealthwandError::ConfigError(msg) => {
+                assert!(msg.contains("pack.yaml") && msg.contains("schema_version 2.0"))
+            }
+            _ => panic!("Expected ConfigError"),
+        }
+    }
+}
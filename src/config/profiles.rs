@@ -0,0 +1,203 @@
+//! Named scan profiles.
+//!
+//! A profile bundles settings for one kind of run: thresholds, pattern packs
+//! (`catalogue_include`), context window and redaction. Four profiles are built in;
+//! config files can define more under `profiles:`, and a file's definition replaces
+//! a built-in or user-config profile of the same name. A profile is selected with
+//! `profile:`, `HEALTHWAND_PROFILE` or `--profile`.
+
+use super::settings::{PartialSettings, SettingSource, SettingsLayer};
+use crate::error::HealthwandError;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// The built-in profiles, in the settings file format.
+pub const BUILT_IN_PROFILES: &str = r#"
+# CI gate: fail only on confident, high-severity findings.
+ci:
+  min_severity: "high"
+  min_score: 0.8
+# Fail on anything, and require a justification on every suppression.
+strict:
+  min_severity: "informational"
+  min_score: 0.0
+  strict_suppressions: true
+# Research pipelines: maximum recall, wide context for escalation rules.
+research:
+  min_score: 0.0
+  context_window: 20
+# Export jobs: redact every finding with a tagged placeholder.
+redact-export:
+  min_score: 0.0
+  redact: true
+  redaction_strategy: "placeholder"
+"#;
+
+/// A profile definition and the file it came from (`None` if built in).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileDefinition {
+    pub settings: PartialSettings,
+    pub defined_in: Option<PathBuf>,
+}
+
+/// Every profile visible from `layers`: the built-in ones, then those defined in
+/// config files, later files replacing earlier definitions.
+pub fn definitions(
+    layers: &[SettingsLayer],
+) -> crate::error::Result<BTreeMap<String, ProfileDefinition>> {
+    let built_in: BTreeMap<String, PartialSettings> = serde_yaml_ng::from_str(BUILT_IN_PROFILES)
+        .map_err(|e| HealthwandError::YamlError(format!("built-in profiles: {}", e)))?;
+    let mut definitions: BTreeMap<String, ProfileDefinition> = built_in
+        .into_iter()
+        .map(|(name, settings)| {
+            let definition = ProfileDefinition {
+                settings,
+                defined_in: None,
+            };
+            (name, definition)
+        })
+        .collect();
+
+    for layer in layers {
+        let Some(profiles) = &layer.values.profiles else {
+            continue;
+        };
+        let defined_in = match &layer.source {
+            SettingSource::UserConfig(path) | SettingSource::RepositoryConfig(path) => {
+                Some(path.clone())
+            }
+            _ => None,
+        };
+        for (name, settings) in profiles {
+            if settings.profile.is_some() || settings.profiles.is_some() {
+                return Err(HealthwandError::ConfigError(format!(
+                    "profile '{}' in {}: a profile cannot select or define profiles",
+                    name,
+                    layer.source.describe("profiles")
+                )));
+            }
+            definitions.insert(
+                name.clone(),
+                ProfileDefinition {
+                    settings: settings.clone(),
+                    defined_in: defined_in.clone(),
+                },
+            );
+        }
+    }
+    Ok(definitions)
+}
+
+/// The layer for the profile selected in `layers`, if any.
+pub(crate) fn select(layers: &[SettingsLayer]) -> crate::error::Result<Option<SettingsLayer>> {
+    let Some((name, source)) = layers
+        .iter()
+        .rev()
+        .find_map(|l| l.values.profile.as_ref().map(|name| (name, &l.source)))
+    else {
+        return Ok(None);
+    };
+    let mut definitions = definitions(layers)?;
+    match definitions.remove(name) {
+        Some(definition) => Ok(Some(SettingsLayer {
+            source: SettingSource::Profile {
+                name: name.clone(),
+                defined_in: definition.defined_in,
+            },
+            values: definition.settings,
+        })),
+        None => Err(HealthwandError::ConfigError(format!(
+            "unknown profile '{}' (from {}); available: {}",
+            name,
+            source.describe("profile"),
+            definitions.keys().cloned().collect::<Vec<_>>().join(", ")
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::settings::resolve;
+    use crate::domain::{RedactionStrategy, Severity};
+
+    fn layer(source: SettingSource, yaml: &str) -> SettingsLayer {
+        SettingsLayer {
+            source,
+            values: serde_yaml_ng::from_str(yaml).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_built_in_profiles() {
+        let names: Vec<String> = definitions(&[]).unwrap().into_keys().collect();
+        assert_eq!(names, vec!["ci", "redact-export", "research", "strict"]);
+
+        let resolved = resolve(&[layer(SettingSource::Cli, "profile: redact-export")]).unwrap();
+        assert!(resolved.settings.redact);
+        assert_eq!(
+            resolved.settings.redaction_strategy,
+            RedactionStrategy::PlaceholderSubstitution
+        );
+        assert_eq!(
+            resolved.source("redact").describe("redact"),
+            "profile redact-export (built-in)"
+        );
+    }
+
+    #[test]
+    fn test_profile_between_files_and_env() {
+        let repo = PathBuf::from(".healthwand.yaml");
+        let resolved = resolve(&[
+            layer(
+                SettingSource::RepositoryConfig(repo.clone()),
+                "profile: ci\nmin_severity: critical\noutput: text\nprofiles:\n  ci:\n    min_severity: medium\n    context_window: 4\n",
+            ),
+            layer(SettingSource::Env, "min_score: 0.5"),
+        ])
+        .unwrap();
+        let s = &resolved.settings;
+        assert_eq!(s.profile.as_deref(), Some("ci"));
+        assert_eq!(s.min_severity, Some(Severity::Medium));
+        assert_eq!(s.context_window, Some(4));
+        assert_eq!(s.min_score.value(), 0.5);
+        assert_eq!(
+            resolved.source("min_severity"),
+            &SettingSource::Profile {
+                name: "ci".into(),
+                defined_in: Some(repo)
+            }
+        );
+        assert_eq!(resolved.source("min_score"), &SettingSource::Env);
+    }
+
+    #[test]
+    fn test_profile_cannot_set_fingerprint_key() {
+        let user = layer(
+            SettingSource::UserConfig(PathBuf::from("config.yaml")),
+            "profile: mine\nprofiles:\n  mine:\n    fingerprint_key: org-secret\n",
+        );
+        match resolve(&[user]).unwrap_err() {
+            HealthwandError::ConfigError(msg) => {
+                assert!(msg.contains("fingerprint_key") && msg.contains("profile mine"));
+            }
+            _ => panic!("Expected ConfigError"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_and_nested_profiles_rejected() {
+        match resolve(&[layer(SettingSource::Cli, "profile: nightly")]).unwrap_err() {
+            HealthwandError::ConfigError(msg) => {
+                assert!(msg.contains("'nightly'") && msg.contains("--profile"));
+                assert!(msg.contains("ci, redact-export, research, strict"));
+            }
+            _ => panic!("Expected ConfigError"),
+        }
+        let nested = layer(
+            SettingSource::UserConfig(PathBuf::from("config.yaml")),
+            "profiles:\n  mine:\n    profile: ci\n",
+        );
+        assert!(definitions(&[nested]).is_err());
+    }
+}
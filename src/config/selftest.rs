@@ -0,0 +1,165 @@
+//! Example self-tests for `healthwand validate`.
+//!
+//! Every `metadata.examples` entry must match its own pattern and every
+//! `metadata.negative_examples` entry must not. Positive examples that other
+//! patterns also match are reported as collisions: they are not failures, since
+//! some overlap is intended (`mrn` and `mrn-generic`), but contributors should see it.
+//!
+//! Examples run against the same compiled [`Pattern`]s the scanner uses, so they
+//! see the merged catalogue rather than any one file.
+
+use crate::config::PatternCatalogue;
+use crate::domain::Pattern;
+use crate::phi_patterns::PHIPatternConfig;
+
+/// An example that did not behave as declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleFailure {
+    pub pattern_id: String,
+    pub example: String,
+    /// `true` for a `negative_examples` entry that matched.
+    pub negative: bool,
+}
+
+/// A positive example of one pattern that other patterns also match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    pub pattern_id: String,
+    pub example: String,
+    pub also_matched_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub examples_checked: usize,
+    pub failures: Vec<ExampleFailure>,
+    pub collisions: Vec<Collision>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Check the examples of resolved catalogue entries against the patterns
+/// `catalogue` built from them.
+pub fn self_test(entries: &[PHIPatternConfig], catalogue: &PatternCatalogue) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+    let compiled: Vec<(&PHIPatternConfig, &Pattern)> = entries
+        .iter()
+        .filter_map(|entry| catalogue.get(&entry.id).map(|pattern| (entry, pattern)))
+        .collect();
+    let matches =
+        |pattern: &Pattern, text: &str| pattern.regex.as_ref().is_some_and(|re| re.is_match(text));
+
+    for (entry, pattern) in &compiled {
+        let metadata = entry.metadata.as_ref();
+        let examples = metadata
+            .and_then(|m| m.examples.as_deref())
+            .unwrap_or_default();
+        let negatives = metadata
+            .and_then(|m| m.negative_examples.as_deref())
+            .unwrap_or_default();
+
+        for example in examples {
+            report.examples_checked += 1;
+            if !matches(pattern, example) {
+                report.failures.push(ExampleFailure {
+                    pattern_id: entry.id.clone(),
+                    example: example.clone(),
+                    negative: false,
+                });
+            }
+            let also_matched_by: Vec<String> = compiled
+                .iter()
+                .filter(|(other, p)| other.id != entry.id && matches(p, example))
+                .map(|(other, _)| other.id.clone())
+                .collect();
+            if !also_matched_by.is_empty() {
+                report.collisions.push(Collision {
+                    pattern_id: entry.id.clone(),
+                    example: example.clone(),
+                    also_matched_by,
+                });
+            }
+        }
+        for example in negatives {
+            report.examples_checked += 1;
+            if matches(pattern, example) {
+                report.failures.push(ExampleFailure {
+                    pattern_id: entry.id.clone(),
+                    example: example.clone(),
+                    negative: true,
+                });
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::validate::CompiledRegexes;
+    use crate::domain::RegexLimits;
+    use crate::phi_patterns::PHIPatternsFile;
+
+    fn run(file: PHIPatternsFile) -> SelfTestReport {
+        let entries = file.resolve().unwrap();
+        let mut regexes = CompiledRegexes::new(RegexLimits::default());
+        let catalogue = crate::config::build_catalogue(entries.clone(), &mut regexes).unwrap();
+        self_test(&entries, &catalogue)
+    }
+
+    #[test]
+    fn test_bundled_examples_pass() {
+        let file = crate::config::parse_document(
+            crate::config::DEFAULT_CATALOGUE,
+            "bundled",
+            &mut CompiledRegexes::new(RegexLimits::default()),
+        )
+        .unwrap();
+        let report = run(file);
+        assert!(report.passed(), "{:?}", report.failures);
+        assert!(report.examples_checked > 0);
+    }
+
+    #[test]
+    fn test_failures_and_collisions() {
+        let report = run(serde_yaml_ng::from_str(
+            r#"
+patterns:
+  - id: "bpjs"
+    name: "BPJS"
+    regex: "\\b\\d{13}\\b"
+    metadata:
+      examples: ["0001234567890", "12345"]
+      negative_examples: ["00012345678901", "1234567890123"]
+  - id: "digits"
+    name: "Digits"
+    regex: "\\b\\d{8,13}\\b"
+"#,
+        )
+        .unwrap());
+        assert_eq!(report.examples_checked, 4);
+        assert_eq!(
+            report.failures,
+            vec![
+                ExampleFailure {
+                    pattern_id: "bpjs".into(),
+                    example: "12345".into(),
+                    negative: false
+                },
+                ExampleFailure {
+                    pattern_id: "bpjs".into(),
+                    example: "1234567890123".into(),
+                    negative: true
+                },
+            ]
+        );
+        assert_eq!(report.collisions.len(), 1);
+        assert_eq!(report.collisions[0].also_matched_by, vec!["digits"]);
+        assert!(!report.passed());
+    }
+}
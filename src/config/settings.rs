@@ -0,0 +1,898 @@
+//! Run settings and their precedence (ARCHITECTURE §3.3).
+//!
+//! Each scan option can be set in several places. From lowest to highest priority:
+//! built-in defaults, the user config (`~/.config/healthwand/config.yaml`), the
+//! repository config (`.healthwand.yaml`), `HEALTHWAND_*` environment variables and
+//! CLI flags. Lists are replaced, not concatenated, by a higher layer. Every
+//! effective value remembers the layer it came from, for `healthwand config show`.
+//!
+//! A selected [profile](super::profiles) sits between the config files and the
+//! environment: it overrides what the files set, and env or flags override it.
+
+use crate::content::Extractor;
+use crate::domain::{RedactionStrategy, RegexLimits, Score, Severity};
+use crate::encoding::{EncodingRule, EncodingRules};
+use crate::error::HealthwandError;
+use crate::file_source::{ArchiveLimits, DEFAULT_MAX_FILE_SIZE};
+use crate::format::OutputFormat;
+use crate::rules::{PathRule, RuleSet};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Repository config, relative to the repository root.
+pub const REPOSITORY_CONFIG: &str = ".healthwand.yaml";
+
+/// `(setting, CLI flag, environment variable)` for every setting, in display order.
+const SETTINGS: &[(&str, &str, &str)] = &[
+    ("profile", "--profile", "HEALTHWAND_PROFILE"),
+    ("catalogue", "--config", "HEALTHWAND_CATALOGUE"),
+    (
+        "catalogue_include",
+        "--config-include",
+        "HEALTHWAND_CATALOGUE_INCLUDE",
+    ),
+    ("include", "--include", "HEALTHWAND_INCLUDE"),
+    ("exclude", "--exclude", "HEALTHWAND_EXCLUDE"),
+    ("hidden", "--hidden", "HEALTHWAND_HIDDEN"),
+    (
+        "follow_symlinks",
+        "--follow-symlinks",
+        "HEALTHWAND_FOLLOW_SYMLINKS",
+    ),
+    (
+        "symlinks_outside_root",
+        "--symlinks-outside-root",
+        "HEALTHWAND_SYMLINKS_OUTSIDE_ROOT",
+    ),
+    ("extensions", "--extension", "HEALTHWAND_EXTENSIONS"),
+    // Only settable in config files and profiles.
+    ("extractors", "", ""),
+    ("encodings", "", ""),
+    ("archives", "--archives", "HEALTHWAND_ARCHIVES"),
+    (
+        "archive_max_depth",
+        "--archive-max-depth",
+        "HEALTHWAND_ARCHIVE_MAX_DEPTH",
+    ),
+    (
+        "archive_max_size",
+        "--archive-max-size",
+        "HEALTHWAND_ARCHIVE_MAX_SIZE",
+    ),
+    (
+        "max_file_size",
+        "--max-file-size",
+        "HEALTHWAND_MAX_FILE_SIZE",
+    ),
+    // Only settable in config files and profiles.
+    ("rules", "", ""),
+    ("min_severity", "--min-severity", "HEALTHWAND_MIN_SEVERITY"),
+    ("min_score", "--min-score", "HEALTHWAND_MIN_SCORE"),
+    (
+        "context_window",
+        "--context-window",
+        "HEALTHWAND_CONTEXT_WINDOW",
+    ),
+    ("output", "--output", "HEALTHWAND_OUTPUT"),
+    ("redact", "--redact", "HEALTHWAND_REDACT"),
+    (
+        "redaction_strategy",
+        "--redaction-strategy",
+        "HEALTHWAND_REDACTION_STRATEGY",
+    ),
+    (
+        "regex_size_limit",
+        "--regex-size-limit",
+        "HEALTHWAND_REGEX_SIZE_LIMIT",
+    ),
+    (
+        "regex_dfa_size_limit",
+        "--regex-dfa-size-limit",
+        "HEALTHWAND_REGEX_DFA_SIZE_LIMIT",
+    ),
+    ("allowlist", "--allowlist", "HEALTHWAND_ALLOWLIST"),
+    ("baseline", "--baseline", "HEALTHWAND_BASELINE"),
+    (
+        "strict_suppressions",
+        "--strict-suppressions",
+        "HEALTHWAND_STRICT_SUPPRESSIONS",
+    ),
+    // Not a flag, so the key does not show up in process listings.
+    ("fingerprint_key", "", "HEALTHWAND_FINGERPRINT_KEY"),
+];
+
+/// Effective settings for a scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Name of the selected profile.
+    pub profile: Option<String>,
+    /// Catalogue replacing the built-in one.
+    pub catalogue: Option<PathBuf>,
+    pub catalogue_include: Vec<PathBuf>,
+    /// Path globs of the files to scan; empty means all.
+    pub include: Vec<String>,
+    /// Path globs of files not to scan.
+    pub exclude: Vec<String>,
+    /// Also scan hidden files and directories.
+    pub hidden: bool,
+    /// Follow symlinks to files and directories under the scanned directory.
+    pub follow_symlinks: bool,
+    /// With `follow_symlinks`, also follow symlinks leading outside it.
+    pub symlinks_outside_root: bool,
+    /// Only scan these extensions; empty means sniff each file's content.
+    pub extensions: Vec<String>,
+    /// Extractor per extension (without the dot).
+    pub extractors: BTreeMap<String, Extractor>,
+    /// Encodings for paths that are not UTF-8 and are not detected correctly.
+    pub encodings: Vec<EncodingRule>,
+    /// Also scan the files inside zip, tar and gzip archives.
+    pub archives: bool,
+    pub archive_limits: ArchiveLimits,
+    /// Files larger than this many bytes are skipped; `None` means no limit.
+    pub max_file_size: Option<u64>,
+    /// Path-scoped pattern rules, applied in order.
+    pub rules: Vec<PathRule>,
+    /// Active findings at or above this fail the scan; `None` never fails it.
+    pub min_severity: Option<Severity>,
+    /// Findings scoring below this are dropped.
+    pub min_score: Score,
+    /// Context-word window for every pattern, replacing the catalogue's.
+    pub context_window: Option<usize>,
+    pub output: OutputFormat,
+    pub redact: bool,
+    pub redaction_strategy: RedactionStrategy,
+    /// Limits for compiling catalogue regexes.
+    pub regex_limits: RegexLimits,
+    pub allowlist: Option<PathBuf>,
+    pub baseline: Option<PathBuf>,
+    pub strict_suppressions: bool,
+    /// HMAC key for finding fingerprints; `None` uses the built-in key.
+    pub fingerprint_key: Option<Secret>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            catalogue: None,
+            catalogue_include: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            hidden: false,
+            follow_symlinks: false,
+            symlinks_outside_root: false,
+            extensions: Vec::new(),
+            extractors: BTreeMap::new(),
+            encodings: Vec::new(),
+            archives: false,
+            archive_limits: ArchiveLimits::default(),
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+            rules: Vec::new(),
+            min_severity: None,
+            min_score: Score::new(0.0).expect("0.0 is a valid score"),
+            context_window: None,
+            output: OutputFormat::Json,
+            redact: false,
+            redaction_strategy: RedactionStrategy::FullReplacement,
+            regex_limits: RegexLimits::default(),
+            allowlist: None,
+            baseline: None,
+            strict_suppressions: false,
+            fingerprint_key: None,
+        }
+    }
+}
+
+/// Settings from one layer; `None` leaves the value to lower layers.
+///
+/// This is also the format of `.healthwand.yaml` and the user config, which may
+/// additionally define named profiles:
+///
+/// ```yaml
+/// profile: "ci"
+/// catalogue_include: [".healthwand/hospital-patterns.yaml"]
+/// exclude: ["**/tests/fixtures/**"]
+/// max_file_size: "10MB"
+/// extractors:
+///   hl7: "text"
+/// output: "sarif"
+/// profiles:
+///   nightly:
+///     min_severity: "medium"
+///     min_score: 0.6
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialSettings {
+    pub profile: Option<String>,
+    /// Profile definitions; only allowed in config files.
+    pub profiles: Option<BTreeMap<String, PartialSettings>>,
+    pub catalogue: Option<PathBuf>,
+    pub catalogue_include: Option<Vec<PathBuf>>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub hidden: Option<bool>,
+    pub follow_symlinks: Option<bool>,
+    pub symlinks_outside_root: Option<bool>,
+    pub extensions: Option<Vec<String>>,
+    pub extractors: Option<BTreeMap<String, Extractor>>,
+    pub encodings: Option<Vec<EncodingRule>>,
+    pub archives: Option<bool>,
+    pub archive_max_depth: Option<usize>,
+    pub archive_max_size: Option<ByteSize>,
+    pub max_file_size: Option<ByteSize>,
+    pub rules: Option<Vec<PathRule>>,
+    pub min_severity: Option<String>,
+    pub min_score: Option<f32>,
+    pub context_window: Option<usize>,
+    pub output: Option<String>,
+    pub redact: Option<bool>,
+    pub redaction_strategy: Option<String>,
+    /// Bytes; see [`RegexLimits`].
+    pub regex_size_limit: Option<usize>,
+    pub regex_dfa_size_limit: Option<usize>,
+    pub allowlist: Option<PathBuf>,
+    pub baseline: Option<PathBuf>,
+    pub strict_suppressions: Option<bool>,
+    pub fingerprint_key: Option<Secret>,
+}
+
+impl PartialSettings {
+    /// Read a config file. Relative paths in it are relative to the file's directory.
+    pub fn from_yaml_file(path: &Path) -> crate::error::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut settings: Self = serde_yaml_ng::from_str(&text)
+            .map_err(|e| HealthwandError::YamlError(format!("{}: {}", path.display(), e)))?;
+        // An empty file parses as null, which serde turns into all-`None`.
+        settings.rebase(path.parent().unwrap_or(Path::new("")));
+        Ok(settings)
+    }
+
+    /// Make relative paths, including those in profiles, relative to `base`.
+    fn rebase(&mut self, base: &Path) {
+        let rebase = |p: &mut PathBuf| {
+            if p.is_relative() {
+                *p = base.join(&*p);
+            }
+        };
+        self.catalogue.as_mut().map(rebase);
+        self.allowlist.as_mut().map(rebase);
+        self.baseline.as_mut().map(rebase);
+        if let Some(includes) = self.catalogue_include.as_mut() {
+            includes.iter_mut().for_each(rebase);
+        }
+        for profile in self.profiles.iter_mut().flat_map(BTreeMap::values_mut) {
+            profile.rebase(base);
+        }
+    }
+
+    /// Read `HEALTHWAND_*` variables through `var`; empty values count as unset.
+    ///
+    /// `HEALTHWAND_INCLUDE`, `HEALTHWAND_EXCLUDE` and `HEALTHWAND_EXTENSIONS` are
+    /// comma-separated and `HEALTHWAND_CATALOGUE_INCLUDE` uses
+    /// the platform path separator, like `PATH`.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> crate::error::Result<Self> {
+        let get = |name: &str| var(name).filter(|v| !v.trim().is_empty());
+        let flag = |name: &str| {
+            get(name)
+                .map(|v| match v.trim().to_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => Ok(true),
+                    "0" | "false" | "no" | "off" => Ok(false),
+                    _ => Err(HealthwandError::ConfigError(format!(
+                        "{}: expected true or false, got '{}'",
+                        name, v
+                    ))),
+                })
+                .transpose()
+        };
+        Ok(Self {
+            profile: get("HEALTHWAND_PROFILE"),
+            profiles: None,
+            catalogue: get("HEALTHWAND_CATALOGUE").map(PathBuf::from),
+            catalogue_include: get("HEALTHWAND_CATALOGUE_INCLUDE")
+                .map(|v| std::env::split_paths(&v).collect()),
+            include: get("HEALTHWAND_INCLUDE").map(|v| comma_list(&v)),
+            exclude: get("HEALTHWAND_EXCLUDE").map(|v| comma_list(&v)),
+            hidden: flag("HEALTHWAND_HIDDEN")?,
+            follow_symlinks: flag("HEALTHWAND_FOLLOW_SYMLINKS")?,
+            symlinks_outside_root: flag("HEALTHWAND_SYMLINKS_OUTSIDE_ROOT")?,
+            extensions: get("HEALTHWAND_EXTENSIONS").map(|v| comma_list(&v)),
+            extractors: None,
+            encodings: None,
+            archives: flag("HEALTHWAND_ARCHIVES")?,
+            archive_max_depth: parse_number(
+                "HEALTHWAND_ARCHIVE_MAX_DEPTH",
+                get("HEALTHWAND_ARCHIVE_MAX_DEPTH"),
+            )?,
+            archive_max_size: get("HEALTHWAND_ARCHIVE_MAX_SIZE").map(ByteSize::Text),
+            max_file_size: get("HEALTHWAND_MAX_FILE_SIZE").map(ByteSize::Text),
+            rules: None,
+            min_severity: get("HEALTHWAND_MIN_SEVERITY"),
+            min_score: parse_number("HEALTHWAND_MIN_SCORE", get("HEALTHWAND_MIN_SCORE"))?,
+            context_window: parse_number(
+                "HEALTHWAND_CONTEXT_WINDOW",
+                get("HEALTHWAND_CONTEXT_WINDOW"),
+            )?,
+            output: get("HEALTHWAND_OUTPUT"),
+            redact: flag("HEALTHWAND_REDACT")?,
+            redaction_strategy: get("HEALTHWAND_REDACTION_STRATEGY"),
+            regex_size_limit: parse_number(
+                "HEALTHWAND_REGEX_SIZE_LIMIT",
+                get("HEALTHWAND_REGEX_SIZE_LIMIT"),
+            )?,
+            regex_dfa_size_limit: parse_number(
+                "HEALTHWAND_REGEX_DFA_SIZE_LIMIT",
+                get("HEALTHWAND_REGEX_DFA_SIZE_LIMIT"),
+            )?,
+            allowlist: get("HEALTHWAND_ALLOWLIST").map(PathBuf::from),
+            baseline: get("HEALTHWAND_BASELINE").map(PathBuf::from),
+            strict_suppressions: flag("HEALTHWAND_STRICT_SUPPRESSIONS")?,
+            fingerprint_key: get("HEALTHWAND_FINGERPRINT_KEY").map(Secret),
+        })
+    }
+}
+
+fn comma_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|g| !g.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// A setting value kept out of `Debug` output and `healthwand config show`.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(pub String);
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+/// A size in bytes, written as a number or with a unit: `"512KB"`, `"50MB"`, `"1GB"`.
+/// Units are binary (`1KB` is 1024 bytes); `0` disables the limit it sets.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ByteSize {
+    Bytes(u64),
+    Text(String),
+}
+
+impl ByteSize {
+    pub fn bytes(&self) -> Result<u64, String> {
+        let text = match self {
+            ByteSize::Bytes(n) => return Ok(*n),
+            ByteSize::Text(text) => text.trim(),
+        };
+        let split = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1 << 10,
+            "M" | "MB" | "MIB" => 1 << 20,
+            "G" | "GB" | "GIB" => 1 << 30,
+            _ => return Err(format!("unknown size unit in '{}'", text)),
+        };
+        number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(multiplier))
+            .ok_or_else(|| format!("expected a size such as 50MB, got '{}'", text))
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(
+    name: &str,
+    value: Option<String>,
+) -> crate::error::Result<Option<T>> {
+    value
+        .map(|v| {
+            v.trim().parse().map_err(|_| {
+                HealthwandError::ConfigError(format!("{}: expected a number, got '{}'", name, v))
+            })
+        })
+        .transpose()
+}
+
+/// Where a setting's value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingSource {
+    Default,
+    UserConfig(PathBuf),
+    RepositoryConfig(PathBuf),
+    /// A named profile and the file defining it (`None` for a built-in profile).
+    Profile {
+        name: String,
+        defined_in: Option<PathBuf>,
+    },
+    Env,
+    Cli,
+}
+
+impl SettingSource {
+    /// Human-readable origin of `setting`, e.g. `env HEALTHWAND_OUTPUT`.
+    pub fn describe(&self, setting: &str) -> String {
+        let (flag, var) = SETTINGS
+            .iter()
+            .find(|(name, ..)| *name == setting)
+            .map_or(("", ""), |(_, flag, var)| (*flag, *var));
+        match self {
+            SettingSource::Default => "default".to_string(),
+            SettingSource::UserConfig(path) => format!("user config ({})", path.display()),
+            SettingSource::RepositoryConfig(path) => {
+                format!("repository config ({})", path.display())
+            }
+            SettingSource::Profile {
+                name,
+                defined_in: None,
+            } => format!("profile {} (built-in)", name),
+            SettingSource::Profile {
+                name,
+                defined_in: Some(path),
+            } => format!("profile {} ({})", name, path.display()),
+            SettingSource::Env => format!("env {}", var),
+            SettingSource::Cli => flag.to_string(),
+        }
+    }
+}
+
+/// One layer of settings, lowest priority first when passed to [`resolve`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsLayer {
+    pub source: SettingSource,
+    pub values: PartialSettings,
+}
+
+/// Effective settings and the source of each value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedSettings {
+    pub settings: Settings,
+    sources: BTreeMap<&'static str, SettingSource>,
+}
+
+impl ResolvedSettings {
+    pub fn source(&self, setting: &str) -> &SettingSource {
+        self.sources.get(setting).unwrap_or(&SettingSource::Default)
+    }
+
+    /// `(setting, value, origin)` for every setting, for `healthwand config show`.
+    pub fn explain(&self) -> Vec<(&'static str, String, String)> {
+        let s = &self.settings;
+        let path = |p: &Option<PathBuf>| {
+            p.as_ref()
+                .map_or_else(|| "-".to_string(), |p| p.display().to_string())
+        };
+        let list = |items: Vec<String>| format!("[{}]", items.join(", "));
+        SETTINGS
+            .iter()
+            .map(|(name, ..)| {
+                let value = match *name {
+                    "profile" => s.profile.clone().unwrap_or_else(|| "-".to_string()),
+                    "catalogue" => s
+                        .catalogue
+                        .as_ref()
+                        .map_or_else(|| "built-in".to_string(), |p| p.display().to_string()),
+                    "catalogue_include" => list(
+                        s.catalogue_include
+                            .iter()
+                            .map(|p| p.display().to_string())
+                            .collect(),
+                    ),
+                    "include" => list(s.include.clone()),
+                    "exclude" => list(s.exclude.clone()),
+                    "hidden" => s.hidden.to_string(),
+                    "follow_symlinks" => s.follow_symlinks.to_string(),
+                    "symlinks_outside_root" => s.symlinks_outside_root.to_string(),
+                    "extensions" if s.extensions.is_empty() => "sniff content".to_string(),
+                    "extensions" => list(s.extensions.clone()),
+                    "extractors" => list(
+                        s.extractors
+                            .iter()
+                            .map(|(ext, x)| format!("{}: {}", ext, x.label()))
+                            .collect(),
+                    ),
+                    "encodings" => list(
+                        s.encodings
+                            .iter()
+                            .map(|e| format!("{}: {}", e.paths.join(" "), e.encoding))
+                            .collect(),
+                    ),
+                    "max_file_size" => s
+                        .max_file_size
+                        .map_or_else(|| "unlimited".to_string(), |n| n.to_string()),
+                    "rules" => list(
+                        s.rules
+                            .iter()
+                            .enumerate()
+                            .map(|(i, r)| r.label(i))
+                            .collect(),
+                    ),
+                    "archives" => s.archives.to_string(),
+                    "archive_max_depth" => s.archive_limits.max_depth.to_string(),
+                    "archive_max_size" => match s.archive_limits.max_total_size {
+                        u64::MAX => "unlimited".to_string(),
+                        n => n.to_string(),
+                    },
+                    "min_severity" => s
+                        .min_severity
+                        .map_or_else(|| "-".to_string(), |m| m.to_string()),
+                    "min_score" => s.min_score.value().to_string(),
+                    "context_window" => s
+                        .context_window
+                        .map_or_else(|| "catalogue".to_string(), |n| n.to_string()),
+                    "output" => s.output.to_string(),
+                    "redact" => s.redact.to_string(),
+                    "redaction_strategy" => s.redaction_strategy.label().to_string(),
+                    "regex_size_limit" => s.regex_limits.size_limit.to_string(),
+                    "regex_dfa_size_limit" => s.regex_limits.dfa_size_limit.to_string(),
+                    "allowlist" => path(&s.allowlist),
+                    "baseline" => path(&s.baseline),
+                    "strict_suppressions" => s.strict_suppressions.to_string(),
+                    "fingerprint_key" => match s.fingerprint_key {
+                        Some(_) => "(set)".to_string(),
+                        None => "built-in".to_string(),
+                    },
+                    _ => String::new(),
+                };
+                (*name, value, self.source(name).describe(name))
+            })
+            .collect()
+    }
+}
+
+/// Merge `layers` (lowest priority first) over the defaults.
+///
+/// If a profile is selected, its values are applied just before the first
+/// environment or CLI layer. Invalid values are reported with the setting and the
+/// layer that set them. `fingerprint_key` is refused from the repository config
+/// and profiles, which are usually committed alongside the code.
+pub fn resolve(layers: &[SettingsLayer]) -> crate::error::Result<ResolvedSettings> {
+    let mut layers = layers.to_vec();
+    if let Some(profile) = super::profiles::select(&layers)? {
+        let at = layers
+            .iter()
+            .position(|l| matches!(l.source, SettingSource::Env | SettingSource::Cli))
+            .unwrap_or(layers.len());
+        layers.insert(at, profile);
+    }
+
+    let mut merged = PartialSettings::default();
+    let mut sources = BTreeMap::new();
+    for layer in &layers {
+        if layer.values.fingerprint_key.is_some()
+            && matches!(
+                layer.source,
+                SettingSource::RepositoryConfig(_) | SettingSource::Profile { .. }
+            )
+        {
+            return Err(HealthwandError::ConfigError(format!(
+                "fingerprint_key from {}: only allowed in the user config or HEALTHWAND_FINGERPRINT_KEY",
+                layer.source.describe("fingerprint_key")
+            )));
+        }
+        macro_rules! take {
+            ($($field:ident),*) => {$(
+                if let Some(value) = &layer.values.$field {
+                    merged.$field = Some(value.clone());
+                    sources.insert(stringify!($field), layer.source.clone());
+                }
+            )*};
+        }
+        take!(
+            profile,
+            catalogue,
+            catalogue_include,
+            include,
+            exclude,
+            hidden,
+            follow_symlinks,
+            symlinks_outside_root,
+            extensions,
+            extractors,
+            encodings,
+            archives,
+            archive_max_depth,
+            archive_max_size,
+            max_file_size,
+            rules,
+            min_severity,
+            min_score,
+            context_window,
+            output,
+            redact,
+            redaction_strategy,
+            regex_size_limit,
+            regex_dfa_size_limit,
+            allowlist,
+            baseline,
+            strict_suppressions,
+            fingerprint_key
+        );
+    }
+
+    let invalid = |setting: &str, e: String| {
+        let source = sources.get(setting).unwrap_or(&SettingSource::Default);
+        HealthwandError::ConfigError(format!(
+            "{} from {}: {}",
+            setting,
+            source.describe(setting),
+            e
+        ))
+    };
+    let defaults = Settings::default();
+    let settings = Settings {
+        profile: merged.profile,
+        catalogue: merged.catalogue,
+        catalogue_include: merged.catalogue_include.unwrap_or_default(),
+        include: merged.include.unwrap_or_default(),
+        exclude: merged.exclude.unwrap_or_default(),
+        hidden: merged.hidden.unwrap_or(defaults.hidden),
+        follow_symlinks: merged.follow_symlinks.unwrap_or(defaults.follow_symlinks),
+        symlinks_outside_root: merged
+            .symlinks_outside_root
+            .unwrap_or(defaults.symlinks_outside_root),
+        extensions: merged
+            .extensions
+            .unwrap_or_default()
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_string())
+            .collect(),
+        extractors: merged
+            .extractors
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(ext, x)| (ext.trim_start_matches('.').to_string(), x))
+            .collect(),
+        encodings: merged.encodings.unwrap_or_default(),
+        archives: merged.archives.unwrap_or(defaults.archives),
+        archive_limits: ArchiveLimits {
+            max_depth: merged
+                .archive_max_depth
+                .unwrap_or(defaults.archive_limits.max_depth),
+            max_total_size: match merged.archive_max_size {
+                Some(size) => match size.bytes().map_err(|e| invalid("archive_max_size", e))? {
+                    0 => u64::MAX,
+                    n => n,
+                },
+                None => defaults.archive_limits.max_total_size,
+            },
+        },
+        max_file_size: match merged.max_file_size {
+            Some(size) => match size.bytes().map_err(|e| invalid("max_file_size", e))? {
+                0 => None,
+                n => Some(n),
+            },
+            None => defaults.max_file_size,
+        },
+        rules: merged.rules.unwrap_or_default(),
+        min_severity: match merged.min_severity {
+            Some(v) => Some(v.parse().map_err(|e| invalid("min_severity", e))?),
+            None => defaults.min_severity,
+        },
+        min_score: match merged.min_score {
+            Some(v) if (0.0..=1.0).contains(&v) => Score::new(v)?,
+            Some(v) => {
+                return Err(invalid(
+                    "min_score",
+                    format!("expected 0.0 to 1.0, got {}", v),
+                ));
+            }
+            None => defaults.min_score,
+        },
+        context_window: merged.context_window,
+        output: match merged.output {
+            Some(v) => v.parse().map_err(|e| invalid("output", e))?,
+            None => defaults.output,
+        },
+        redact: merged.redact.unwrap_or(defaults.redact),
+        redaction_strategy: match merged.redaction_strategy {
+            Some(v) => v.parse().map_err(|e| invalid("redaction_strategy", e))?,
+            None => defaults.redaction_strategy,
+        },
+        regex_limits: RegexLimits {
+            size_limit: merged
+                .regex_size_limit
+                .unwrap_or(defaults.regex_limits.size_limit),
+            dfa_size_limit: merged
+                .regex_dfa_size_limit
+                .unwrap_or(defaults.regex_limits.dfa_size_limit),
+        },
+        allowlist: merged.allowlist,
+        baseline: merged.baseline,
+        strict_suppressions: merged
+            .strict_suppressions
+            .unwrap_or(defaults.strict_suppressions),
+        fingerprint_key: merged.fingerprint_key,
+    };
+    for (setting, globs) in [
+        ("include", &settings.include),
+        ("exclude", &settings.exclude),
+    ] {
+        for glob in globs {
+            globset::Glob::new(glob).map_err(|e| invalid(setting, e.to_string()))?;
+        }
+    }
+    EncodingRules::new(&settings.encodings).map_err(|e| match e {
+        HealthwandError::ConfigError(msg) => invalid("encodings", msg),
+        other => other,
+    })?;
+    RuleSet::new(&settings.rules).map_err(|e| match e {
+        HealthwandError::ConfigError(msg) => invalid("rules", msg),
+        other => other,
+    })?;
+    Ok(ResolvedSettings { settings, sources })
+}
+
+/// `~/.config/healthwand/config.yaml` (or under `$XDG_CONFIG_HOME`).
+pub fn user_config_path() -> Option<PathBuf> {
+    super::user_config_dir().map(|dir| dir.join("config.yaml"))
+}
+
+/// Read the user and repository config files that exist, lowest priority first.
+pub fn file_layers(repo_root: &Path) -> crate::error::Result<Vec<SettingsLayer>> {
+    let mut layers = Vec::new();
+    if let Some(path) = user_config_path().filter(|p| p.is_file()) {
+        layers.push(SettingsLayer {
+            values: PartialSettings::from_yaml_file(&path)?,
+            source: SettingSource::UserConfig(path),
+        });
+    }
+    let path = repo_root.join(REPOSITORY_CONFIG);
+    if path.is_file() {
+        layers.push(SettingsLayer {
+            values: PartialSettings::from_yaml_file(&path)?,
+            source: SettingSource::RepositoryConfig(path),
+        });
+    }
+    Ok(layers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn layer(source: SettingSource, values: PartialSettings) -> SettingsLayer {
+        SettingsLayer { source, values }
+    }
+
+    #[test]
+    fn test_precedence_and_sources() {
+        let dir = TempDir::new().unwrap();
+        let repo = dir.path().join(REPOSITORY_CONFIG);
+        std::fs::write(
+            &repo,
+            "output: sarif\nmin_severity: medium\nexclude: ['**/fixtures/**']\nallowlist: allow.yaml\n",
+        )
+        .unwrap();
+        let env = PartialSettings::from_env(|name| match name {
+            "HEALTHWAND_MIN_SEVERITY" => Some("high".to_string()),
+            "HEALTHWAND_REDACT" => Some("yes".to_string()),
+            "HEALTHWAND_OUTPUT" => Some(String::new()),
+            "HEALTHWAND_MAX_FILE_SIZE" => Some("512kb".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        let cli = PartialSettings {
+            min_severity: Some("critical".to_string()),
+            ..Default::default()
+        };
+        let resolved = resolve(&[
+            layer(
+                SettingSource::RepositoryConfig(repo.clone()),
+                PartialSettings::from_yaml_file(&repo).unwrap(),
+            ),
+            layer(SettingSource::Env, env),
+            layer(SettingSource::Cli, cli),
+        ])
+        .unwrap();
+
+        let s = &resolved.settings;
+        assert_eq!(s.output, OutputFormat::Sarif);
+        assert_eq!(s.min_severity, Some(Severity::Critical));
+        assert!(s.redact);
+        assert_eq!(s.exclude, vec!["**/fixtures/**"]);
+        assert_eq!(s.max_file_size, Some(512 * 1024));
+        assert!(!s.hidden);
+        assert_eq!(s.allowlist, Some(dir.path().join("allow.yaml")));
+        assert_eq!(s.redaction_strategy, RedactionStrategy::FullReplacement);
+
+        assert_eq!(resolved.source("min_severity"), &SettingSource::Cli);
+        assert_eq!(resolved.source("redact"), &SettingSource::Env);
+        assert!(matches!(
+            resolved.source("output"),
+            SettingSource::RepositoryConfig(_)
+        ));
+        let explained = resolved.explain();
+        let redact = explained.iter().find(|(n, ..)| *n == "redact").unwrap();
+        assert_eq!(redact.2, "env HEALTHWAND_REDACT");
+        let strategy = explained
+            .iter()
+            .find(|(n, ..)| *n == "redaction_strategy")
+            .unwrap();
+        assert_eq!(
+            (strategy.1.as_str(), strategy.2.as_str()),
+            ("full", "default")
+        );
+    }
+
+    #[test]
+    fn test_invalid_value_names_setting_and_source() {
+        let env = PartialSettings::from_env(|name| {
+            (name == "HEALTHWAND_OUTPUT").then(|| "xml".to_string())
+        })
+        .unwrap();
+        match resolve(&[layer(SettingSource::Env, env)]).unwrap_err() {
+            HealthwandError::ConfigError(msg) => {
+                assert!(msg.contains("output from env HEALTHWAND_OUTPUT"))
+            }
+            _ => panic!("Expected ConfigError"),
+        }
+        assert!(
+            PartialSettings::from_env(|_| Some("maybe".to_string())).is_err(),
+            "non-boolean HEALTHWAND_REDACT must be rejected"
+        );
+        assert!(ByteSize::Text("50XB".to_string()).bytes().is_err());
+        let unlimited = PartialSettings {
+            max_file_size: Some(ByteSize::Bytes(0)),
+            ..Default::default()
+        };
+        let resolved = resolve(&[layer(SettingSource::Cli, unlimited)]).unwrap();
+        assert_eq!(resolved.settings.max_file_size, None);
+    }
+
+    #[test]
+    fn test_fingerprint_key_is_not_shown() {
+        let dir = TempDir::new().unwrap();
+        let user = dir.path().join("config.yaml");
+        std::fs::write(&user, "fingerprint_key: org-secret\n").unwrap();
+        let values = PartialSettings::from_yaml_file(&user).unwrap();
+        assert!(!format!("{:?}", values).contains("org-secret"));
+        let resolved = resolve(&[layer(SettingSource::UserConfig(user), values)]).unwrap();
+        assert_eq!(
+            resolved.settings.fingerprint_key,
+            Some(Secret("org-secret".to_string()))
+        );
+        let explained = resolved.explain();
+        let key = explained
+            .iter()
+            .find(|(n, ..)| *n == "fingerprint_key")
+            .unwrap();
+        assert_eq!(key.1, "(set)");
+        assert_eq!(resolve(&[]).unwrap().settings.fingerprint_key, None);
+    }
+
+    #[test]
+    fn test_fingerprint_key_rejected_from_repository_config() {
+        let values = PartialSettings {
+            fingerprint_key: Some(Secret("org-secret".to_string())),
+            ..Default::default()
+        };
+        let repo = SettingSource::RepositoryConfig(PathBuf::from(REPOSITORY_CONFIG));
+        match resolve(&[layer(repo, values.clone())]).unwrap_err() {
+            HealthwandError::ConfigError(msg) => {
+                assert!(msg.contains("fingerprint_key") && msg.contains(REPOSITORY_CONFIG));
+                assert!(!msg.contains("org-secret"));
+            }
+            _ => panic!("Expected ConfigError"),
+        }
+        assert!(resolve(&[layer(SettingSource::Env, values)]).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_key_in_file_rejected() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(REPOSITORY_CONFIG);
+        std::fs::write(&path, "min_severty: high\n").unwrap();
+        match PartialSettings::from_yaml_file(&path).unwrap_err() {
+            HealthwandError::YamlError(msg) => assert!(msg.contains("min_severty")),
+            _ => panic!("Expected YamlError"),
+        }
+    }
+}
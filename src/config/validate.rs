@@ -0,0 +1,418 @@
+//! Catalogue validation.
+//!
+//! Each catalogue file is checked against `config/phi_patterns.schema.json` and then
+//! semantically: regexes compile within the configured [`RegexLimits`], IDs are
+//! unique within the file, and examples behave as declared. Diagnostics carry the
+//! line and column of the offending node and the pattern ID, so errors point into
+//! the file rather than at a serde path.
+//!
+//! Examples that do not match are warnings rather than errors, so a stale example
+//! does not stop every scan from loading the catalogue; `healthwand validate`
+//! prints them.
+
+use crate::domain::RegexLimits;
+use crate::error::HealthwandError;
+use jsonschema::error::ValidationErrorKind;
+use regex::Regex;
+use serde_yaml_ng::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::LazyLock;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+/// JSON Schema shipped with the repository.
+pub const CATALOGUE_SCHEMA: &str = include_str!("../../config/phi_patterns.schema.json");
+
+static VALIDATOR: LazyLock<jsonschema::Validator> = LazyLock::new(|| {
+    let schema: serde_json::Value =
+        serde_json::from_str(CATALOGUE_SCHEMA).expect("bundled catalogue schema is valid JSON");
+    jsonschema::validator_for(&schema).expect("bundled catalogue schema compiles")
+});
+
+/// One problem found in a catalogue file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based line of the offending node (0 if it could not be located).
+    pub line: usize,
+    /// 1-based column of the offending node.
+    pub column: usize,
+    pub pattern_id: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        if let Some(id) = &self.pattern_id {
+            write!(f, "pattern '{}': ", id)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Diagnostics for one catalogue file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validation {
+    /// Problems that stop the catalogue from loading.
+    pub errors: Vec<Diagnostic>,
+    /// Examples that do not behave as declared.
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Catalogue regexes compiled within one set of limits, keyed by source.
+///
+/// Validation fills it and building the catalogue reads from it, so each regex is
+/// compiled once per load.
+#[derive(Debug)]
+pub(crate) struct CompiledRegexes {
+    limits: RegexLimits,
+    compiled: HashMap<String, Regex>,
+}
+
+impl CompiledRegexes {
+    pub(crate) fn new(limits: RegexLimits) -> Self {
+        Self {
+            limits,
+            compiled: HashMap::new(),
+        }
+    }
+
+    /// The compiled `source`, naming `pattern_id` in any error.
+    pub(crate) fn compile(
+        &mut self,
+        pattern_id: &str,
+        source: &str,
+    ) -> crate::error::Result<Regex> {
+        if let Some(regex) = self.compiled.get(source) {
+            return Ok(regex.clone());
+        }
+        let regex = self.limits.compile(pattern_id, source)?;
+        self.compiled.insert(source.to_string(), regex.clone());
+        Ok(regex)
+    }
+}
+
+/// Validate one catalogue file, compiling its regexes within `limits`. `text` is
+/// the source `doc` was parsed from.
+pub fn validate_document(text: &str, doc: &Value, limits: &RegexLimits) -> Validation {
+    validate(text, doc, &mut CompiledRegexes::new(*limits))
+}
+
+pub(crate) fn validate(text: &str, doc: &Value, regexes: &mut CompiledRegexes) -> Validation {
+    let locator = Locator::new(text);
+    let mut validation = Validation::default();
+    let locate = |pointer: &str, message: String| {
+        let (line, column) = locator.position(pointer);
+        Diagnostic {
+            line,
+            column,
+            pattern_id: pattern_id_at(doc, pointer),
+            message,
+        }
+    };
+    let mut report =
+        |pointer: &str, message: String| validation.errors.push(locate(pointer, message));
+
+    match serde_json::to_value(doc) {
+        Ok(instance) => {
+            for error in VALIDATOR.iter_errors(&instance) {
+                let mut pointer = error.instance_path().to_string();
+                // Point at the unexpected key rather than its parent mapping.
+                if let ValidationErrorKind::AdditionalProperties { unexpected } = error.kind() {
+                    if let Some(key) = unexpected.first() {
+                        pointer = format!("{}/{}", pointer, escape(key));
+                    }
+                }
+                report(&pointer, error.to_string());
+            }
+        }
+        Err(e) => report("", format!("not representable as JSON: {}", e)),
+    }
+
+    let patterns = doc
+        .get("patterns")
+        .and_then(Value::as_sequence)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (i, pattern) in patterns.iter().enumerate() {
+        let base = format!("/patterns/{}", i);
+        let id = pattern.get("id").and_then(Value::as_str);
+        if let Some(id) = id {
+            if let Some(first) = seen.get(id) {
+                let (line, _) = locator.position(&format!("/patterns/{}/id", first));
+                report(
+                    &format!("{}/id", base),
+                    format!("duplicate id (first defined on line {})", line),
+                );
+            } else {
+                seen.insert(id, i);
+            }
+        }
+
+        let Some(source) = pattern.get("regex").and_then(Value::as_str) else {
+            continue;
+        };
+        let regex = match regexes.compile(id.unwrap_or_default(), source) {
+            Ok(regex) => regex,
+            Err(HealthwandError::RegexError { source: e, .. }) => {
+                let detail = e.to_string();
+                let detail = detail.lines().last().unwrap_or_default().trim().to_string();
+                let message = match e {
+                    regex::Error::CompiledTooBig(_) => format!("regex over the limits: {}", detail),
+                    _ => format!("invalid regex: {}", detail),
+                };
+                report(&format!("{}/regex", base), message);
+                continue;
+            }
+            Err(e) => {
+                report(&format!("{}/regex", base), e.to_string());
+                continue;
+            }
+        };
+
+        for (key, negative) in [("examples", false), ("negative_examples", true)] {
+            let examples = pattern
+                .get("metadata")
+                .and_then(|m| m.get(key))
+                .and_then(Value::as_sequence)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for (j, example) in examples.iter().enumerate() {
+                let Some(example) = example.as_str() else {
+                    continue;
+                };
+                if regex.is_match(example) == negative {
+                    let message = if negative {
+                        format!("negative example {:?} matches the regex", example)
+                    } else {
+                        format!("example {:?} does not match the regex", example)
+                    };
+                    validation
+                        .warnings
+                        .push(locate(&format!("{}/metadata/{}/{}", base, key, j), message));
+                }
+            }
+        }
+    }
+    validation
+}
+
+/// Turn diagnostics into a single [`HealthwandError::ConfigError`], one line per problem.
+pub(crate) fn to_error(source: &str, diagnostics: &[Diagnostic]) -> HealthwandError {
+    HealthwandError::ConfigError(
+        diagnostics
+            .iter()
+            .map(|d| format!("{}:{}", source, d))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+/// Pattern ID for a pointer under `/patterns/N` or `/overrides/<id>`.
+fn pattern_id_at(doc: &Value, pointer: &str) -> Option<String> {
+    let mut parts = pointer.trim_start_matches('/').split('/');
+    match (parts.next(), parts.next()) {
+        (Some("patterns"), Some(index)) => doc
+            .get("patterns")?
+            .get(index.parse::<usize>().ok()?)?
+            .get("id")?
+            .as_str()
+            .map(str::to_string),
+        (Some("overrides"), Some(id)) => Some(unescape(id)),
+        _ => None,
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+/// Maps JSON pointers to the position of the YAML node they address.
+struct Locator {
+    positions: HashMap<String, (usize, usize)>,
+}
+
+enum Frame {
+    Sequence { path: String, next: usize },
+    Mapping { path: String, key: Option<String> },
+}
+
+#[derive(Default)]
+struct Walker {
+    positions: HashMap<String, (usize, usize)>,
+    stack: Vec<Frame>,
+}
+
+impl Locator {
+    fn new(text: &str) -> Self {
+        let mut walker = Walker::default();
+        // Syntax errors are reported by serde before validation runs.
+        let _ = Parser::new_from_str(text).load(&mut walker, false);
+        Self {
+            positions: walker.positions,
+        }
+    }
+
+    /// Position of `pointer`, falling back to its nearest located ancestor.
+    fn position(&self, pointer: &str) -> (usize, usize) {
+        let mut pointer = pointer;
+        loop {
+            if let Some(&position) = self.positions.get(pointer) {
+                return position;
+            }
+            match pointer.rfind('/') {
+                Some(i) => pointer = &pointer[..i],
+                None => return (0, 0),
+            }
+        }
+    }
+}
+
+impl Walker {
+    /// Path of the node that starts now, or `None` if it is a mapping key.
+    fn node_path(&mut self, event: &Event) -> Option<String> {
+        match self.stack.last_mut() {
+            None => Some(String::new()),
+            Some(Frame::Sequence { path, next }) => {
+                *next += 1;
+                Some(format!("{}/{}", path, *next - 1))
+            }
+            Some(Frame::Mapping { path, key }) => match key.take() {
+                Some(k) => Some(format!("{}/{}", path, escape(&k))),
+                None => {
+                    *key = Some(match event {
+                        Event::Scalar(value, ..) => value.clone(),
+                        _ => String::new(),
+                    });
+                    None
+                }
+            },
+        }
+    }
+}
+
+impl MarkedEventReceiver for Walker {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let position = (mark.line(), mark.col() + 1);
+        match &event {
+            Event::Scalar(..) | Event::Alias(..) => {
+                if let Some(path) = self.node_path(&event) {
+                    self.positions.insert(path, position);
+                } else if let Some(Frame::Mapping { path, key: Some(k) }) = self.stack.last() {
+                    // Keys locate their value until the value itself is seen.
+                    self.positions
+                        .insert(format!("{}/{}", path, escape(k)), position);
+                }
+            }
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                let path = self.node_path(&event).unwrap_or_default();
+                self.positions.entry(path.clone()).or_insert(position);
+                self.stack.push(match event {
+                    Event::SequenceStart(..) => Frame::Sequence { path, next: 0 },
+                    _ => Frame::Mapping { path, key: None },
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validation(yaml: &str) -> Validation {
+        let doc: Value = serde_yaml_ng::from_str(yaml).unwrap();
+        validate_document(yaml, &doc, &RegexLimits::default())
+    }
+
+    fn check(yaml: &str) -> Vec<Diagnostic> {
+        validation(yaml).errors
+    }
+
+    #[test]
+    fn test_bundled_catalogue_is_valid() {
+        let text = std::str::from_utf8(crate::config::DEFAULT_CATALOGUE).unwrap();
+        assert_eq!(validation(text), Validation::default());
+    }
+
+    #[test]
+    fn test_schema_error_has_line_column_and_id() {
+        let yaml = "schema_version: \"1.0\"\npatterns:\n  - id: \"mrn-generic\"\n    name: \"MRN\"\n    regex: \"\\\\d{8}\"\n    context: []\n";
+        let diagnostics = check(yaml);
+        assert_eq!(diagnostics.len(), 1);
+        let d = &diagnostics[0];
+        assert_eq!((d.line, d.column), (6, 5));
+        assert_eq!(d.pattern_id.as_deref(), Some("mrn-generic"));
+        assert!(d.message.contains("object"));
+    }
+
+    #[test]
+    fn test_semantic_checks() {
+        let yaml = r#"
+patterns:
+  - id: "a"
+    name: "A"
+    regex: "\\bA\\d{3}\\b"
+    metadata:
+      examples: ["A123", "B456"]
+      negative_examples:
+        - "A999"
+  - id: "a"
+    name: "Again"
+    regex: "(unclosed"
+"#;
+        let validation = validation(yaml);
+        let messages: Vec<String> = validation.errors.iter().map(|d| d.to_string()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].starts_with("10:") && messages[0].contains("duplicate id"));
+        assert!(messages[1].starts_with("12:") && messages[1].contains("invalid regex"));
+
+        // Mismatched examples are warnings, so they do not stop the catalogue loading.
+        let warnings: Vec<String> = validation.warnings.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "7:26: pattern 'a': example \"B456\" does not match the regex",
+                "9:11: pattern 'a': negative example \"A999\" matches the regex",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_regexes_compile_within_limits_once() {
+        let yaml =
+            "patterns:\n  - id: \"greedy\"\n    name: \"Greedy\"\n    regex: \"\\\\w{20}\"\n";
+        let doc: Value = serde_yaml_ng::from_str(yaml).unwrap();
+        let limits = RegexLimits {
+            size_limit: 10_000,
+            ..RegexLimits::default()
+        };
+        let errors = validate_document(yaml, &doc, &limits).errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (4, 12));
+        assert!(errors[0].message.starts_with("regex over the limits"));
+
+        let mut regexes = CompiledRegexes::new(RegexLimits::default());
+        assert!(validate(yaml, &doc, &mut regexes).errors.is_empty());
+        assert_eq!(regexes.compiled.len(), 1);
+        regexes.compile("greedy", "\\w{20}").unwrap();
+        assert_eq!(regexes.compiled.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_key_located() {
+        let diagnostics = check("patterns: []\nbogus: 1\n");
+        assert_eq!(diagnostics[0].line, 2);
+        assert!(diagnostics[0].message.contains("bogus"));
+    }
+}
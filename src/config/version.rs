@@ -0,0 +1,282 @@
+//! Catalogue `schema_version` handling (ARCHITECTURE §5.1).
+//!
+//! The loader accepts any minor version of the current major, warning when a file
+//! declares a newer minor than this build understands, and rejects other majors.
+//! Files written before versioning (no `schema_version`) are read as the current
+//! version with a warning; [`migrate`] upgrades them.
+
+use crate::error::HealthwandError;
+use serde::{Deserialize, Deserializer};
+use serde_yaml_ng::{Mapping, Value};
+use std::fmt;
+use std::str::FromStr;
+use tracing::warn;
+
+/// Schema version this build reads and writes.
+pub const CURRENT_SCHEMA_VERSION: SchemaVersion = SchemaVersion { major: 1, minor: 0 };
+
+/// `major.minor` of a catalogue schema. A patch component is accepted and ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SchemaVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for SchemaVersion {
+    type Err = HealthwandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            HealthwandError::ConfigError(format!(
+                "invalid schema_version '{}': expected \"major.minor\"",
+                s
+            ))
+        };
+        let mut parts = s.trim().split('.');
+        let major = parts
+            .next()
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())?;
+        let minor = match parts.next() {
+            Some(p) => p.parse().map_err(|_| invalid())?,
+            None => 0,
+        };
+        if let Some(patch) = parts.next() {
+            patch.parse::<u32>().map_err(|_| invalid())?;
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self { major, minor })
+    }
+}
+
+impl<'de> Deserialize<'de> for SchemaVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // `schema_version: 1.0` (unquoted) arrives as a number.
+        let value = Value::deserialize(deserializer)?;
+        let text = match &value {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            _ => return Err(serde::de::Error::custom("schema_version must be a string")),
+        };
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for SchemaVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Read `schema_version` from a parsed catalogue document.
+pub fn declared_version(doc: &Value) -> crate::error::Result<Option<SchemaVersion>> {
+    match doc.get("schema_version") {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => serde_yaml_ng::from_value(v.clone())
+            .map(Some)
+            .map_err(|e| HealthwandError::ConfigError(e.to_string())),
+    }
+}
+
+/// Check a declared version against [`CURRENT_SCHEMA_VERSION`].
+///
+/// `source` names the file in warnings; errors are left for the caller to prefix.
+pub fn check_version(declared: Option<SchemaVersion>, source: &str) -> crate::error::Result<()> {
+    let current = CURRENT_SCHEMA_VERSION;
+    match declared {
+        None => warn!(
+            "{}: no schema_version, assuming {} (run `healthwand catalogue migrate` to add it)",
+            source, current
+        ),
+        Some(v) if v.major != current.major => {
+            let hint = if v.major < current.major {
+                "; run `healthwand catalogue migrate` to upgrade it"
+            } else {
+                "; upgrade healthwand to read it"
+            };
+            return Err(HealthwandError::ConfigError(format!(
+                "schema_version {} is not supported (this build reads {}.x){}",
+                v, current.major, hint
+            )));
+        }
+        Some(v) if v.minor > current.minor => warn!(
+            "{}: schema_version {} is newer than {}; fields this build does not know may be ignored",
+            source, v, current
+        ),
+        Some(_) => {}
+    }
+    Ok(())
+}
+
+/// Result of [`migrate`]: the upgraded YAML and a description of each change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub yaml: String,
+    pub changes: Vec<String>,
+}
+
+/// Upgrade a catalogue to [`CURRENT_SCHEMA_VERSION`].
+///
+/// Unversioned (pre-1.0) files get a `schema_version`, list-style `context: [..]`
+/// becomes `context.before`, pattern-level `severity`/`category` move under
+/// `metadata`, and severity `low` becomes `informational`. Comments are not preserved.
+pub fn migrate(yaml: &str) -> crate::error::Result<Migration> {
+    let mut doc: Value =
+        serde_yaml_ng::from_str(yaml).map_err(|e| HealthwandError::YamlError(e.to_string()))?;
+    let declared = declared_version(&doc)?;
+    if let Some(v) = declared.filter(|v| v.major > CURRENT_SCHEMA_VERSION.major) {
+        return Err(HealthwandError::ConfigError(format!(
+            "schema_version {} is newer than {}; cannot migrate backwards",
+            v, CURRENT_SCHEMA_VERSION
+        )));
+    }
+    let Value::Mapping(root) = &mut doc else {
+        return Err(HealthwandError::ConfigError(
+            "catalogue must be a mapping".to_string(),
+        ));
+    };
+
+    let mut changes = Vec::new();
+    if let Some(Value::Sequence(patterns)) = root.get_mut("patterns") {
+        for pattern in patterns.iter_mut() {
+            if let Value::Mapping(pattern) = pattern {
+                migrate_pattern(pattern, &mut changes);
+            }
+        }
+    }
+    if declared != Some(CURRENT_SCHEMA_VERSION) {
+        changes.push(format!(
+            "schema_version {} -> {}",
+            declared.map_or("(none)".to_string(), |v| v.to_string()),
+            CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    // Put schema_version first, as in the bundled catalogue.
+    let mut upgraded = Mapping::new();
+    upgraded.insert(
+        "schema_version".into(),
+        CURRENT_SCHEMA_VERSION.to_string().into(),
+    );
+    for (key, value) in std::mem::take(root) {
+        if key.as_str() != Some("schema_version") {
+            upgraded.insert(key, value);
+        }
+    }
+    let yaml = serde_yaml_ng::to_string(&Value::Mapping(upgraded))
+        .map_err(|e| HealthwandError::YamlError(e.to_string()))?;
+    Ok(Migration { yaml, changes })
+}
+
+fn migrate_pattern(pattern: &mut Mapping, changes: &mut Vec<String>) {
+    let id = pattern
+        .get("id")
+        .and_then(Value::as_str)
+        .unwrap_or("?")
+        .to_string();
+
+    if let Some(Value::Sequence(words)) = pattern.get("context").cloned() {
+        let mut context = Mapping::new();
+        context.insert("before".into(), Value::Sequence(words));
+        context.insert("after".into(), Value::Sequence(Vec::new()));
+        pattern.insert("context".into(), Value::Mapping(context));
+        changes.push(format!("{}: context list -> context.before", id));
+    }
+
+    for key in ["severity", "category"] {
+        if let Some(value) = pattern.remove(key) {
+            let metadata = pattern
+                .entry("metadata".into())
+                .or_insert_with(|| Value::Mapping(Mapping::new()));
+            if let Value::Mapping(metadata) = metadata {
+                metadata.entry(key.into()).or_insert(value);
+                changes.push(format!("{}: {} -> metadata.{}", id, key, key));
+            }
+        }
+    }
+
+    // Pre-1.0 catalogues used `low` for what is now `informational`.
+    if let Some(Value::Mapping(metadata)) = pattern.get_mut("metadata") {
+        if metadata.get("severity").and_then(Value::as_str) == Some("low") {
+            metadata.insert("severity".into(), "informational".into());
+            changes.push(format!("{}: severity low -> informational", id));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!(
+            "1.0".parse::<SchemaVersion>().unwrap(),
+            CURRENT_SCHEMA_VERSION
+        );
+        assert_eq!(
+            "1.2.3".parse::<SchemaVersion>().unwrap(),
+            SchemaVersion { major: 1, minor: 2 }
+        );
+        assert!("one".parse::<SchemaVersion>().is_err());
+        let unquoted: Value = serde_yaml_ng::from_str("schema_version: 1.0").unwrap();
+        assert_eq!(
+            declared_version(&unquoted).unwrap(),
+            Some(CURRENT_SCHEMA_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_check_version_rules() {
+        assert!(check_version(Some(CURRENT_SCHEMA_VERSION), "a.yaml").is_ok());
+        assert!(check_version(None, "a.yaml").is_ok());
+        assert!(check_version(Some(SchemaVersion { major: 1, minor: 9 }), "a.yaml").is_ok());
+        match check_version(Some(SchemaVersion { major: 2, minor: 0 }), "a.yaml").unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("2.0")),
+            _ => panic!("Expected ConfigError"),
+        }
+    }
+
+    #[test]
+    fn test_migrate_legacy_catalogue() {
+        let legacy = r#"
+patterns:
+  - id: "mrn-generic"
+    name: "MRN"
+    regex: "\\b\\d{8,12}\\b"
+    context: ["rm"]
+    severity: "high"
+  - id: "icd10"
+    name: "ICD-10"
+    regex: "\\b[A-Z]\\d{2}\\b"
+    metadata:
+      severity: "low"
+"#;
+        let migration = migrate(legacy).unwrap();
+        assert_eq!(migration.changes.len(), 4);
+        assert!(
+            migration
+                .changes
+                .contains(&"icd10: severity low -> informational".to_string())
+        );
+        assert!(migration.yaml.starts_with("schema_version: '1.0'"));
+        let catalogue = crate::config::load_yaml(migration.yaml.as_bytes()).unwrap();
+        let mrn = catalogue.get("mrn-generic").unwrap();
+        assert_eq!(mrn.default_severity, crate::domain::Severity::High);
+        assert!(mrn.context_words.contains(&"rm".to_string()));
+        assert_eq!(
+            catalogue.get("icd10").unwrap().default_severity,
+            crate::domain::Severity::Informational
+        );
+        assert!(migrate("schema_version: \"2.0\"\npatterns: []\n").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Deciding whether a file is text, and turning its bytes into scannable text.
+//!
+//! Files are sniffed by content rather than extension: a byte-order mark means
+//! text, a NUL byte means binary, and otherwise the share of control characters in
+//! the first [`SNIFF_LEN`] bytes decides. An extension list can replace sniffing,
+//! and an extractor can be configured per extension:
+//!
+//! ```yaml
+//! extractors:
+//!   hl7: text        # always scan, even with MLLP control bytes
+//!   sqlite: strings  # scan printable runs of a binary file
+//!   pdf: skip        # never scan
+//! ```
+
+use crate::encoding::DecodedText;
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// Bytes inspected when sniffing a file.
+pub const SNIFF_LEN: usize = 8192;
+
+/// Sniffed text with more control characters than this percentage is binary.
+const MAX_CONTROL_PERCENT: usize = 10;
+
+pub const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+pub const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+pub const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// What sniffing concluded about a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Text,
+    Binary,
+}
+
+/// Classify `bytes` (only the first [`SNIFF_LEN`] are looked at).
+pub fn sniff(bytes: &[u8]) -> ContentKind {
+    let head = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if [UTF8_BOM, UTF16LE_BOM, UTF16BE_BOM]
+        .iter()
+        .any(|bom| head.starts_with(bom))
+    {
+        return ContentKind::Text;
+    }
+    if head.contains(&0) {
+        return ContentKind::Binary;
+    }
+    let control = head.iter().filter(|&&b| is_control(b)).count();
+    if control * 100 > head.len() * MAX_CONTROL_PERCENT {
+        ContentKind::Binary
+    } else {
+        ContentKind::Text
+    }
+}
+
+/// Control bytes that do not occur in ordinary text (tab, newlines, form feed and
+/// escape do).
+fn is_control(b: u8) -> bool {
+    (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B)) || b == 0x7F
+}
+
+/// How a file's bytes become text for scanning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Extractor {
+    /// Sniff the content; scan it as text unless it looks binary.
+    Auto,
+    /// Always scan as text.
+    Text,
+    /// Scan the printable ASCII runs of a binary file. Every other byte becomes a
+    /// newline, so offsets still point into the original file.
+    Strings,
+    /// Never scan.
+    Skip,
+}
+
+impl Extractor {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Extractor::Auto => "auto",
+            Extractor::Text => "text",
+            Extractor::Strings => "strings",
+            Extractor::Skip => "skip",
+        }
+    }
+
+    /// Text to scan from `bytes`, or `None` if the file is not scanned.
+    ///
+    /// `encoding` is the file's configured encoding, if any; such a file is text
+    /// (UTF-16 without a byte-order mark would otherwise sniff as binary).
+    pub fn extract(
+        &self,
+        bytes: Vec<u8>,
+        encoding: Option<&'static Encoding>,
+    ) -> io::Result<Option<DecodedText>> {
+        match self {
+            Extractor::Skip => Ok(None),
+            Extractor::Auto if encoding.is_none() && sniff(&bytes) == ContentKind::Binary => {
+                Ok(None)
+            }
+            Extractor::Auto | Extractor::Text => DecodedText::decode(&bytes, encoding).map(Some),
+            Extractor::Strings => Ok(Some(printable_runs(bytes).into())),
+        }
+    }
+}
+
+fn printable_runs(mut bytes: Vec<u8>) -> String {
+    for b in &mut bytes {
+        if !(b.is_ascii_graphic() || matches!(*b, b' ' | b'\t' | b'\n' | b'\r')) {
+            *b = b'\n';
+        }
+    }
+    String::from_utf8(bytes).expect("only ASCII bytes remain")
+}
+
+/// Which files are scanned and with which extractor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileTypes {
+    /// If non-empty, only these extensions are scanned (as text) and sniffing is off.
+    pub extensions: Vec<String>,
+    /// Extractor per extension (without the dot), taking precedence over `extensions`.
+    pub extractors: BTreeMap<String, Extractor>,
+}
+
+impl FileTypes {
+    pub fn new(extensions: Vec<String>) -> Self {
+        Self {
+            extensions,
+            extractors: BTreeMap::new(),
+        }
+    }
+
+    /// The extractor for `path`, from its extension.
+    pub fn extractor(&self, path: &Path) -> Extractor {
+        let ext = path.extension().and_then(|e| e.to_str());
+        if let Some(extractor) = ext.and_then(|ext| {
+            self.extractors
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(ext))
+                .map(|(_, extractor)| *extractor)
+        }) {
+            return extractor;
+        }
+        let listed = |ext: &str| self.extensions.iter().any(|x| x.eq_ignore_ascii_case(ext));
+        if self.extensions.is_empty() {
+            Extractor::Auto
+        } else if ext.is_some_and(listed) {
+            Extractor::Text
+        } else {
+            Extractor::Skip
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(
+            sniff(b"MSH|^~\\&|SIMRS\rPID|||3171234567890123\r"),
+            ContentKind::Text
+        );
+        assert_eq!(sniff(b""), ContentKind::Text);
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), ContentKind::Binary);
+        assert_eq!(sniff(&[0x01, 0x02, 0x03, b'a', b'b']), ContentKind::Binary);
+        assert_eq!(sniff(b"\xFF\xFEN\0I\0K\0"), ContentKind::Text);
+        assert_eq!(
+            sniff("Pasien: Siti Nurhaliza".as_bytes()),
+            ContentKind::Text
+        );
+    }
+
+    #[test]
+    fn test_extract() {
+        let utf16: Vec<u8> = UTF16LE_BOM
+            .iter()
+            .copied()
+            .chain("NIK 3171".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let text = |extractor: Extractor, bytes: &[u8]| {
+            extractor
+                .extract(bytes.to_vec(), None)
+                .unwrap()
+                .map(|decoded| decoded.text)
+        };
+        assert_eq!(text(Extractor::Auto, &utf16).as_deref(), Some("NIK 3171"));
+        assert_eq!(text(Extractor::Auto, b"a\0b"), None);
+        assert_eq!(
+            text(Extractor::Strings, b"\0\x01SSN 123-45-6789\0").as_deref(),
+            Some("\n\nSSN 123-45-6789\n")
+        );
+        assert_eq!(text(Extractor::Skip, b"text"), None);
+        let utf16le_without_bom = Extractor::Auto
+            .extract(b"N\0I\0K\0".to_vec(), Some(encoding_rs::UTF_16LE))
+            .unwrap();
+        assert_eq!(utf16le_without_bom.unwrap().text, "NIK");
+    }
+
+    #[test]
+    fn test_file_types() {
+        let mut types = FileTypes::default();
+        assert_eq!(types.extractor(Path::new("a.json")), Extractor::Auto);
+        types.extensions = vec!["txt".to_string()];
+        types
+            .extractors
+            .insert("db".to_string(), Extractor::Strings);
+        assert_eq!(types.extractor(Path::new("a.TXT")), Extractor::Text);
+        assert_eq!(types.extractor(Path::new("Makefile")), Extractor::Skip);
+        assert_eq!(types.extractor(Path::new("x.db")), Extractor::Strings);
+    }
+}
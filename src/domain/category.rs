@@ -26,3 +26,16 @@ impl std::fmt::Display for Category {
         }
     }
 }
+
+impl Category {
+    /// Parse a catalogue label (case-insensitive); unknown labels become `Other`.
+    pub fn from_label(label: &str) -> Self {
+        match label.to_lowercase().as_str() {
+            "identifier" => Category::Identifier,
+            "medical" => Category::Medical,
+            "personal" => Category::Personal,
+            "insurance" => Category::Insurance,
+            other => Category::Other(other.to_string()),
+        }
+    }
+}
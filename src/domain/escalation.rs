@@ -0,0 +1,202 @@
+use crate::domain::{Finding, MatchSpan, PatternId, Severity};
+use std::ops::Range;
+
+/// Context-driven severity rules for a pattern.
+///
+/// The same regex match can mean very different things in a clinic note and in a
+/// medical textbook. Rules are evaluated in order; the first rule whose signals
+/// appear within `window` bytes of the match decides the severity. If no rule
+/// fires, `otherwise` applies, falling back to the pattern's default severity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escalation {
+    /// Radius around the match to inspect. `None` uses the pattern's context window.
+    pub window: Option<usize>,
+    pub rules: Vec<EscalationRule>,
+    /// Severity when no rule fires (e.g., `Informational` for a bare ICD-10 code).
+    pub otherwise: Option<Severity>,
+}
+
+/// A single escalation rule. Fires if ANY listed context word or co-located pattern is present.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscalationRule {
+    pub context_words: Vec<String>,
+    pub co_located: Vec<PatternId>,
+    pub severity: Severity,
+}
+
+impl EscalationRule {
+    fn fires(&self, window_text: &str, nearby: &[&PatternId]) -> bool {
+        let lowered = window_text.to_lowercase();
+        self.context_words
+            .iter()
+            .any(|w| contains_word(&lowered, &w.to_lowercase()))
+            || self.co_located.iter().any(|id| nearby.contains(&id))
+    }
+}
+
+impl Escalation {
+    /// Resolve the severity of `finding` given the scanned `text` and every finding in it.
+    ///
+    /// `default_window` is used when the escalation does not declare its own window.
+    pub fn resolve(
+        &self,
+        default: Severity,
+        default_window: usize,
+        text: &str,
+        finding: &Finding,
+        all: &SpanIndex<'_>,
+    ) -> Severity {
+        let window = self.window.unwrap_or(default_window);
+        let window_text = finding.span.surrounding(text, window);
+        let lo = finding.span.start.saturating_sub(window);
+        let hi = finding.span.end.saturating_add(window);
+        let nearby: Vec<&PatternId> = all
+            .overlapping(lo..hi)
+            .filter(|other| other.pattern_id != finding.pattern_id)
+            .map(|other| &other.pattern_id)
+            .collect();
+
+        self.rules
+            .iter()
+            .find(|rule| rule.fires(window_text, &nearby))
+            .map(|rule| rule.severity)
+            .or(self.otherwise)
+            .unwrap_or(default)
+    }
+}
+
+/// The findings of one text sorted by start offset, so those near a match are
+/// found by binary search rather than by checking every finding.
+#[derive(Debug)]
+pub struct SpanIndex<'a> {
+    sorted: Vec<&'a Finding>,
+    /// Length of the longest span; nothing starting further before a window can reach it.
+    longest: usize,
+}
+
+impl<'a> SpanIndex<'a> {
+    pub fn new(findings: &'a [Finding]) -> Self {
+        let mut sorted: Vec<&Finding> = findings.iter().collect();
+        sorted.sort_by_key(|f| f.span.start);
+        let longest = sorted
+            .iter()
+            .map(|f| f.span.end - f.span.start)
+            .max()
+            .unwrap_or(0);
+        Self { sorted, longest }
+    }
+
+    /// Findings whose span overlaps `window`.
+    pub fn overlapping(&self, window: Range<usize>) -> impl Iterator<Item = &'a Finding> + '_ {
+        let from = window.start.saturating_sub(self.longest);
+        let first = self.sorted.partition_point(|f| f.span.start < from);
+        let last = self.sorted.partition_point(|f| f.span.start < window.end);
+        self.sorted[first..last.max(first)]
+            .iter()
+            .copied()
+            .filter(move |f| overlaps(&f.span, &window))
+    }
+}
+
+fn overlaps(span: &MatchSpan, window: &Range<usize>) -> bool {
+    span.start < window.end && span.end > window.start
+}
+
+/// Case-sensitive whole-word search: `needle` must not be flanked by alphanumerics.
+///
+/// Callers lowercase both sides for case-insensitive matching.
+pub(crate) fn contains_word(haystack: &str, needle: &str) -> bool {
+    if needle.is_empty() {
+        return false;
+    }
+    haystack.match_indices(needle).any(|(i, _)| {
+        let before = haystack[..i].chars().next_back();
+        let after = haystack[i + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::slice;
+
+    fn icd10_escalation() -> Escalation {
+        Escalation {
+            window: Some(40),
+            rules: vec![EscalationRule {
+                context_words: vec!["diagnosis".to_string(), "kode ICD".to_string()],
+                co_located: vec![PatternId::new("indonesian_nik".to_string()).unwrap()],
+                severity: Severity::Critical,
+            }],
+            otherwise: Some(Severity::Informational),
+        }
+    }
+
+    #[test]
+    fn test_context_word_escalates() {
+        let text = "Diagnosis: I21.0 confirmed";
+        let f = Finding::for_test("icd10", text, "I21.0");
+        let severity = icd10_escalation().resolve(
+            Severity::Medium,
+            5,
+            text,
+            &f,
+            &SpanIndex::new(slice::from_ref(&f)),
+        );
+        assert_eq!(severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_co_located_pattern_escalates() {
+        let text = "NIK 0000000000000001 I21.0";
+        let icd = Finding::for_test("icd10", text, "I21.0");
+        let nik = Finding::for_test("indonesian_nik", text, "0000000000000001");
+        let all = vec![nik, icd.clone()];
+        let severity =
+            icd10_escalation().resolve(Severity::Medium, 5, text, &icd, &SpanIndex::new(&all));
+        assert_eq!(severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_span_index_finds_long_spans_starting_before_the_window() {
+        let text = "aaaaaaaaaaaaaaaaaaaa b c d";
+        let all = vec![
+            Finding::for_test("d", text, "d"),
+            Finding::for_test("long", text, "aaaaaaaaaaaaaaaaaaaa"),
+            Finding::for_test("b", text, "b"),
+            Finding::for_test("c", text, "c"),
+        ];
+        let index = SpanIndex::new(&all);
+        let ids = |window: Range<usize>| -> Vec<&str> {
+            index
+                .overlapping(window)
+                .map(|f| f.pattern_id.as_str())
+                .collect()
+        };
+        assert_eq!(ids(19..22), vec!["long", "b"]);
+        assert_eq!(ids(22..24), vec!["c"]);
+        assert_eq!(ids(30..40), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_no_signal_falls_back_to_otherwise() {
+        let text = "Chapter 9 of the textbook covers I21.0 in depth.";
+        let f = Finding::for_test("icd10", text, "I21.0");
+        let severity = icd10_escalation().resolve(
+            Severity::Medium,
+            5,
+            text,
+            &f,
+            &SpanIndex::new(slice::from_ref(&f)),
+        );
+        assert_eq!(severity, Severity::Informational);
+    }
+
+    #[test]
+    fn test_context_word_requires_word_boundary() {
+        assert!(contains_word("kode icd: a09", "kode icd"));
+        assert!(!contains_word("undiagnosisable", "diagnosis"));
+        assert!(!contains_word("anything", ""));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::domain::{MatchSpan, PatternId, RedactionStrategy, Score, Severity};
+use crate::domain::{MatchSpan, PartialMask, PatternId, RedactionStrategy, Score, Severity};
 
 /// A single PHI finding: detected pattern + location + metadata.
 ///
@@ -17,15 +17,120 @@ pub struct Finding {
     pub uu_pdp_article: Option<UuPdpArticle>,
     pub redaction_template: Option<String>,
     pub redaction_strategy: Option<RedactionStrategy>,
+    #[serde(default)]
+    pub partial_mask: PartialMask,
+    pub suppression: Option<Suppression>,
+    /// Stable identity across edits; see [`crate::fingerprint`].
+    pub fingerprint: Option<String>,
+    /// Path rules that affected this finding; see [`crate::rules`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<String>,
 }
 
-/// UU PDP article classification (Indonesian regulatory).
+impl Finding {
+    /// True if an inline directive suppresses this finding (and strict mode did not reject it).
+    pub fn is_suppressed(&self) -> bool {
+        self.suppression.as_ref().is_some_and(|s| !s.rejected)
+    }
+}
+
+#[cfg(test)]
+impl Finding {
+    /// A high-severity finding of pattern `id` on the first `needle` in `text`.
+    pub(crate) fn for_test(id: &str, text: &str, needle: &str) -> Self {
+        let start = text.find(needle).unwrap();
+        Self {
+            pattern_id: PatternId::new(id.to_string()).unwrap(),
+            span: MatchSpan::from_offsets(text, start, start + needle.len()).unwrap(),
+            matched_text: needle.to_string(),
+            severity: Severity::High,
+            score: Score::new(0.95).unwrap(),
+            context: None,
+            context_matched: false,
+            uu_pdp_article: None,
+            redaction_template: None,
+            redaction_strategy: None,
+            partial_mask: PartialMask::default(),
+            suppression: None,
+            fingerprint: None,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// An inline `healthwand:ignore` directive that covers a finding.
+///
+/// Suppressed findings are still reported, so reviewers can audit what was waved through.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Suppression {
+    /// Line (1-based) of the directive comment.
+    pub directive_line: u32,
+    /// Free-text reason written after the directive, if any.
+    pub justification: Option<String>,
+    /// Strict mode rejected this suppression because it has no justification.
+    pub rejected: bool,
+}
+
+/// UU PDP (Law 27/2022) article a pattern's data falls under.
 ///
-/// Stub for M2: all findings get `None`.
-/// Full implementation in M3 when pattern catalogue is complete.
+/// Citations follow `docs/phi-taxonomy-id.md`. In YAML and JSON the article is
+/// written as it is cited, e.g. `"4(1)"`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum UuPdpArticle {
-    // TODO: Add actual article variants in M3
-    Article1,
-    // ... more articles
+    /// Art. 1: personal data in general (identifiable alone or in combination).
+    #[serde(rename = "1")]
+    Art1,
+    /// Art. 4(1): specific personal data, e.g. health data.
+    #[serde(rename = "4(1)")]
+    Art4_1,
+    /// Art. 4(1)d: children's data.
+    #[serde(rename = "4(1)d")]
+    Art4_1d,
+    /// Art. 4(1)e: personal financial data.
+    #[serde(rename = "4(1)e")]
+    Art4_1e,
+    /// Art. 4(2): general personal data, e.g. NIK.
+    #[serde(rename = "4(2)")]
+    Art4_2,
+}
+
+impl UuPdpArticle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UuPdpArticle::Art1 => "1",
+            UuPdpArticle::Art4_1 => "4(1)",
+            UuPdpArticle::Art4_1d => "4(1)d",
+            UuPdpArticle::Art4_1e => "4(1)e",
+            UuPdpArticle::Art4_2 => "4(2)",
+        }
+    }
+}
+
+impl std::fmt::Display for UuPdpArticle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Art. {}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for UuPdpArticle {
+    type Err = String;
+
+    /// Accepts `4(1)`, `Art 4(1)` and `Art. 4(1)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cited = s
+            .trim()
+            .trim_start_matches("Art.")
+            .trim_start_matches("Art")
+            .trim();
+        [
+            UuPdpArticle::Art1,
+            UuPdpArticle::Art4_1,
+            UuPdpArticle::Art4_1d,
+            UuPdpArticle::Art4_1e,
+            UuPdpArticle::Art4_2,
+        ]
+        .into_iter()
+        .find(|a| a.as_str() == cited)
+        .ok_or_else(|| format!("unknown UU PDP article '{}'", s))
+    }
 }
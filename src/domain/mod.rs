@@ -7,6 +7,7 @@
 //! allowing adapters (scanner, config, format) to remain decoupled.
 
 pub mod category;
+pub mod escalation;
 pub mod finding;
 pub mod pattern;
 pub mod score;
@@ -15,8 +16,9 @@ pub mod span;
 
 // Re-exports for convenience at crate::domain::* level
 pub use category::Category;
-pub use finding::{Finding, UuPdpArticle};
-pub use pattern::{DetectorType, Pattern, PatternId, RedactionStrategy};
+pub use escalation::{Escalation, EscalationRule, SpanIndex};
+pub use finding::{Finding, Suppression, UuPdpArticle};
+pub use pattern::{DetectorType, PartialMask, Pattern, PatternId, RedactionStrategy, RegexLimits};
 pub use score::Score;
 pub use severity::Severity;
-pub use span::MatchSpan;
+pub use span::{LineIndex, MatchSpan};
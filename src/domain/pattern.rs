@@ -1,5 +1,5 @@
-use crate::domain::{Category, Score, Severity};
-use regex::Regex;
+use crate::domain::{Category, Escalation, Finding, Score, Severity, SpanIndex, UuPdpArticle};
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
 
 /// Strategy for how to redact matched PHI in output text.
@@ -14,6 +14,92 @@ pub enum RedactionStrategy {
     PlaceholderSubstitution,
 }
 
+impl RedactionStrategy {
+    /// Short name used in settings and the CLI: `full`, `partial` or `placeholder`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RedactionStrategy::FullReplacement => "full",
+            RedactionStrategy::PartialMasking => "partial",
+            RedactionStrategy::PlaceholderSubstitution => "placeholder",
+        }
+    }
+}
+
+impl std::str::FromStr for RedactionStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(RedactionStrategy::FullReplacement),
+            "partial" => Ok(RedactionStrategy::PartialMasking),
+            "placeholder" => Ok(RedactionStrategy::PlaceholderSubstitution),
+            _ => Err(format!(
+                "unknown redaction strategy: {} (expected full, partial or placeholder)",
+                s
+            )),
+        }
+    }
+}
+
+/// What partial masking leaves visible of a pattern's matches. Separators such as
+/// `-`, `/` and `.` are always kept.
+///
+/// Written in YAML as `last:<n>`, `first:<n>` or `year`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum PartialMask {
+    /// The last `n` letters and digits, e.g. `***-**-6789`.
+    Last(usize),
+    /// The first `n` letters and digits, e.g. `B**.*`.
+    First(usize),
+    /// Only the year of a date, e.g. `**/**/2000`.
+    Year,
+}
+
+impl Default for PartialMask {
+    fn default() -> Self {
+        PartialMask::Last(4)
+    }
+}
+
+impl std::fmt::Display for PartialMask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PartialMask::Last(n) => write!(f, "last:{}", n),
+            PartialMask::First(n) => write!(f, "first:{}", n),
+            PartialMask::Year => write!(f, "year"),
+        }
+    }
+}
+
+impl std::str::FromStr for PartialMask {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid mask: {} (expected last:<n>, first:<n> or year)", s);
+        match s.split_once(':') {
+            Some(("last", n)) => n.parse().map(PartialMask::Last).map_err(|_| invalid()),
+            Some(("first", n)) => n.parse().map(PartialMask::First).map_err(|_| invalid()),
+            None if s == "year" => Ok(PartialMask::Year),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl From<PartialMask> for String {
+    fn from(mask: PartialMask) -> Self {
+        mask.to_string()
+    }
+}
+
+impl TryFrom<String> for PartialMask {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 /// Unique identifier for a pattern (e.g., "ssn", "mrn-generic").
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -65,6 +151,55 @@ pub enum DetectorType {
     Nlp,
 }
 
+impl std::fmt::Display for DetectorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DetectorType::Regex => "regex",
+            DetectorType::RegexWithContext => "regex_with_context",
+            DetectorType::Dictionary => "dictionary",
+            DetectorType::Combinatorial => "combinatorial",
+            DetectorType::Nlp => "nlp",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Compile-time limits for catalogue regexes.
+///
+/// The regex engine matches in linear time, so a pattern's cost is bounded by the
+/// size of its compiled program and lazy DFA. Capping both keeps a contributed
+/// pattern such as `\w{1000}` from slowing every scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegexLimits {
+    /// Maximum size in bytes of a compiled regex.
+    pub size_limit: usize,
+    /// Maximum cache size in bytes of the lazy DFA, per search thread.
+    pub dfa_size_limit: usize,
+}
+
+impl Default for RegexLimits {
+    fn default() -> Self {
+        Self {
+            size_limit: 1 << 20,
+            dfa_size_limit: 2 << 20,
+        }
+    }
+}
+
+impl RegexLimits {
+    /// Compile `source` within these limits, naming `pattern_id` in any error.
+    pub fn compile(&self, pattern_id: &str, source: &str) -> crate::error::Result<Regex> {
+        RegexBuilder::new(source)
+            .size_limit(self.size_limit)
+            .dfa_size_limit(self.dfa_size_limit)
+            .build()
+            .map_err(|source| crate::error::HealthwandError::RegexError {
+                pattern: Some(pattern_id.to_string()),
+                source,
+            })
+    }
+}
+
 /// A PHI detection pattern: regex, dictionary, or NLP-based rule.
 #[derive(Debug, Clone)]
 pub struct Pattern {
@@ -79,6 +214,11 @@ pub struct Pattern {
     pub context_window: usize,
     pub redaction_template: Option<String>,
     pub redaction_strategy: Option<RedactionStrategy>,
+    /// What partial masking leaves visible.
+    pub partial_mask: PartialMask,
+    pub escalation: Option<Escalation>,
+    /// UU PDP article copied onto this pattern's findings.
+    pub uu_pdp_article: Option<UuPdpArticle>,
 }
 
 impl Pattern {
@@ -104,6 +244,22 @@ impl Pattern {
         self.validate()?;
         Ok(self)
     }
+
+    /// Severity of one of this pattern's findings, after applying escalation rules.
+    ///
+    /// `all` indexes every finding in `text` (including `finding` itself), used for co-location.
+    pub fn resolve_severity(&self, text: &str, finding: &Finding, all: &SpanIndex<'_>) -> Severity {
+        match &self.escalation {
+            Some(escalation) => escalation.resolve(
+                self.default_severity,
+                self.context_window,
+                text,
+                finding,
+                all,
+            ),
+            None => self.default_severity,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -124,6 +280,9 @@ mod tests {
             context_window: 10,
             redaction_template: None,
             redaction_strategy: None,
+            partial_mask: PartialMask::default(),
+            escalation: None,
+            uu_pdp_article: None,
         };
 
         let result = pattern.validate();
@@ -150,6 +309,9 @@ mod tests {
             context_window: 20,
             redaction_template: None,
             redaction_strategy: None,
+            partial_mask: PartialMask::default(),
+            escalation: None,
+            uu_pdp_article: None,
         };
 
         let result = pattern.validate();
@@ -176,6 +338,9 @@ mod tests {
             context_window: 5,
             redaction_template: None,
             redaction_strategy: None,
+            partial_mask: PartialMask::default(),
+            escalation: None,
+            uu_pdp_article: None,
         };
 
         assert!(pattern.validate().is_ok());
@@ -195,6 +360,9 @@ mod tests {
             context_window: 10,
             redaction_template: None,
             redaction_strategy: None,
+            partial_mask: PartialMask::default(),
+            escalation: None,
+            uu_pdp_article: None,
         };
 
         let result = pattern.validated();
@@ -221,6 +389,9 @@ mod tests {
             context_window: 10,
             redaction_template: None,
             redaction_strategy: None,
+            partial_mask: PartialMask::default(),
+            escalation: None,
+            uu_pdp_article: None,
         };
 
         let result = pattern.validated();
@@ -14,8 +14,8 @@ pub struct MatchSpan {
 impl MatchSpan {
     /// Create a MatchSpan from byte offsets, validating bounds.
     ///
-    /// **Placeholder implementation**: line is always 1, column is byte offset + 1.
-    /// Real line/column tracking (with newline counting and UTF-8 awareness) is deferred to M3.
+    /// Line is always 1 and column is byte offset + 1; use [`LineIndex::span`] for
+    /// real line and column numbers.
     ///
     /// Validates that `start <= end` and both offsets are within text bounds.
     pub fn from_offsets(text: &str, start: usize, end: usize) -> crate::error::Result<Self> {
@@ -39,6 +39,54 @@ impl MatchSpan {
             column: start as u32 + 1,
         })
     }
+
+    /// Slice of `text` extending `radius` bytes either side of the match,
+    /// widened to the nearest UTF-8 character boundaries.
+    pub fn surrounding<'a>(&self, text: &'a str, radius: usize) -> &'a str {
+        let mut left = self.start.saturating_sub(radius).min(text.len());
+        let mut right = usize::min(text.len(), self.end.saturating_add(radius));
+
+        while left > 0 && !text.is_char_boundary(left) {
+            left -= 1;
+        }
+
+        while right < text.len() && !text.is_char_boundary(right) {
+            right += 1;
+        }
+
+        &text[left..right]
+    }
+}
+
+/// Line starts of a text, for turning byte offsets into line and column numbers.
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    /// Byte offset of the start of every line after the first.
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        Self {
+            starts: text.match_indices('\n').map(|(i, _)| i + 1).collect(),
+        }
+    }
+
+    /// Like [`MatchSpan::from_offsets`], with the line and column (in characters)
+    /// of `start`.
+    pub fn span(&self, text: &str, start: usize, end: usize) -> crate::error::Result<MatchSpan> {
+        let span = MatchSpan::from_offsets(text, start, end)?;
+        let line = self.starts.partition_point(|&s| s <= start);
+        let line_start = line.checked_sub(1).map_or(0, |i| self.starts[i]);
+        let column = text
+            .get(line_start..start)
+            .map_or(start - line_start, |prefix| prefix.chars().count());
+        Ok(MatchSpan {
+            line: line as u32 + 1,
+            column: column as u32 + 1,
+            ..span
+        })
+    }
 }
 
 #[cfg(test)]
@@ -83,6 +131,20 @@ mod tests {
         assert_eq!(span.column, 1);
     }
 
+    #[test]
+    fn test_line_index_span() {
+        let text = "Pasien: Siti\r\nNIK: 3171234567890123\n\nNama: José 123-45-6789";
+        let lines = LineIndex::new(text);
+        let nik = text.find("3171").unwrap();
+        let span = lines.span(text, nik, nik + 16).unwrap();
+        assert_eq!((span.line, span.column), (2, 6));
+        let ssn = text.find("123-").unwrap();
+        let span = lines.span(text, ssn, ssn + 11).unwrap();
+        assert_eq!((span.line, span.column), (4, 12));
+        assert_eq!(lines.span(text, 0, 0).unwrap().line, 1);
+        assert!(lines.span(text, 5, 2).is_err());
+    }
+
     #[test]
     fn test_from_offsets_accepts_equal_start_end() {
         let text = "test";
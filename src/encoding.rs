@@ -0,0 +1,320 @@
+//! Decoding files for scanning, and mapping offsets back to the original bytes.
+//!
+//! Exports from older SIMRS systems are often Windows-1252, Latin-1 or UTF-16
+//! rather than UTF-8. Files are decoded to UTF-8 before scanning; a [`DecodedText`]
+//! remembers the original encoding, so findings can be reported at their offsets in
+//! the original bytes and redacted text written back in the same encoding.
+//!
+//! A file's encoding comes from the first of: an `encodings:` rule matching its
+//! path, a byte-order mark, valid UTF-8, or a guess from its byte statistics.
+//!
+//! ```yaml
+//! encodings:
+//!   - paths: ["exports/simrs/**"]
+//!     encoding: "windows-1252"
+//! ```
+//!
+//! Labels are WHATWG encoding labels, so `latin1` and `iso-8859-1` decode as
+//! Windows-1252, their superset.
+
+use crate::domain::MatchSpan;
+use crate::error::HealthwandError;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// Decoded bytes between entries of the offset map.
+const CHECKPOINT_INTERVAL: usize = 4096;
+
+/// Text decoded from a file, with what is needed to map back to the file's bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedText {
+    pub text: String,
+    encoding: &'static Encoding,
+    /// Length of the byte-order mark the file started with, or 0.
+    bom_len: usize,
+    /// `(decoded, original)` offsets at char boundaries roughly every
+    /// [`CHECKPOINT_INTERVAL`] decoded bytes; empty for UTF-8.
+    checkpoints: Vec<(usize, usize)>,
+}
+
+impl From<String> for DecodedText {
+    /// Text that was UTF-8 without a byte-order mark.
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            encoding: UTF_8,
+            bom_len: 0,
+            checkpoints: Vec::new(),
+        }
+    }
+}
+
+impl DecodedText {
+    /// Decode `bytes` as `encoding`, or detect the encoding if `None`.
+    ///
+    /// Malformed input is an `InvalidData` error rather than replacement
+    /// characters, which would break the offset mapping.
+    pub fn decode(bytes: &[u8], encoding: Option<&'static Encoding>) -> io::Result<Self> {
+        let (encoding, bom_len) = match (Encoding::for_bom(bytes), encoding) {
+            (Some((bom, len)), None) => (bom, len),
+            (Some((bom, len)), Some(configured)) if bom == configured => (bom, len),
+            (_, Some(configured)) => (configured, 0),
+            (None, None) => (detect(bytes), 0),
+        };
+        let text = encoding
+            .decode_without_bom_handling_and_without_replacement(&bytes[bom_len..])
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("not valid {}", encoding.name()),
+                )
+            })?
+            .into_owned();
+        let mut decoded = Self {
+            text,
+            encoding,
+            bom_len,
+            checkpoints: Vec::new(),
+        };
+        if encoding != UTF_8 {
+            decoded.checkpoints = decoded.build_checkpoints();
+        }
+        Ok(decoded)
+    }
+
+    /// Name of the original encoding, e.g. `windows-1252`.
+    pub fn encoding_name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    /// Offset in the original bytes of the decoded offset `decoded`, which must be
+    /// a char boundary.
+    pub fn original_offset(&self, decoded: usize) -> usize {
+        if self.encoding == UTF_8 {
+            return self.bom_len + decoded;
+        }
+        let i = self
+            .checkpoints
+            .partition_point(|&(d, _)| d <= decoded)
+            .saturating_sub(1);
+        let (from, original) = self
+            .checkpoints
+            .get(i)
+            .copied()
+            .unwrap_or((0, self.bom_len));
+        original
+            + self.text[from..decoded]
+                .chars()
+                .map(|c| self.encoded_len(c))
+                .sum::<usize>()
+    }
+
+    /// `span` with its byte offsets moved to the original bytes.
+    pub fn original_span(&self, span: MatchSpan) -> MatchSpan {
+        MatchSpan {
+            start: self.original_offset(span.start),
+            end: self.original_offset(span.end),
+            ..span
+        }
+    }
+
+    /// `text` (e.g. a redacted copy of [`text`](Self::text)) in the original
+    /// encoding, with the original byte-order mark.
+    ///
+    /// Characters the encoding cannot represent become HTML numeric character
+    /// references, as in `encoding_rs`; redaction masks and placeholders are ASCII.
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        let mut bytes = match self.bom_len {
+            0 => Vec::with_capacity(text.len()),
+            _ => bom(self.encoding).to_vec(),
+        };
+        if self.encoding == UTF_16LE {
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        } else if self.encoding == UTF_16BE {
+            bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        } else {
+            bytes.extend_from_slice(&self.encoding.encode(text).0);
+        }
+        bytes
+    }
+
+    fn encoded_len(&self, c: char) -> usize {
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            c.len_utf16() * 2
+        } else if self.encoding.is_single_byte() {
+            1
+        } else {
+            let mut buf = [0; 4];
+            self.encoding.encode(c.encode_utf8(&mut buf)).0.len()
+        }
+    }
+
+    fn build_checkpoints(&self) -> Vec<(usize, usize)> {
+        let mut checkpoints = vec![(0, self.bom_len)];
+        let mut original = self.bom_len;
+        let mut next = CHECKPOINT_INTERVAL;
+        for (decoded, c) in self.text.char_indices() {
+            if decoded >= next {
+                checkpoints.push((decoded, original));
+                next = decoded + CHECKPOINT_INTERVAL;
+            }
+            original += self.encoded_len(c);
+        }
+        checkpoints
+    }
+}
+
+fn bom(encoding: &'static Encoding) -> &'static [u8] {
+    if encoding == UTF_16LE {
+        crate::content::UTF16LE_BOM
+    } else if encoding == UTF_16BE {
+        crate::content::UTF16BE_BOM
+    } else {
+        crate::content::UTF8_BOM
+    }
+}
+
+/// UTF-8 if `bytes` are valid UTF-8, otherwise the most likely legacy encoding.
+fn detect(bytes: &[u8]) -> &'static Encoding {
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, false)
+}
+
+/// One `encodings:` entry as written in a settings file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncodingRule {
+    /// Globs of the files in this encoding.
+    pub paths: Vec<String>,
+    /// WHATWG encoding label, e.g. `windows-1252` or `utf-16le`.
+    pub encoding: String,
+}
+
+/// Compiled `encodings:` rules; the last rule matching a path wins.
+#[derive(Debug, Clone, Default)]
+pub struct EncodingRules {
+    rules: Vec<(GlobSet, &'static Encoding)>,
+}
+
+impl EncodingRules {
+    pub fn new(rules: &[EncodingRule]) -> crate::error::Result<Self> {
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let invalid = |msg: String| {
+                    HealthwandError::ConfigError(format!("encodings[{}]: {}", i, msg))
+                };
+                let encoding = Encoding::for_label(rule.encoding.trim().as_bytes())
+                    .ok_or_else(|| invalid(format!("unknown encoding '{}'", rule.encoding)))?;
+                let mut builder = GlobSetBuilder::new();
+                for glob in &rule.paths {
+                    builder.add(Glob::new(glob).map_err(|e| invalid(e.to_string()))?);
+                }
+                let paths = builder.build().map_err(|e| invalid(e.to_string()))?;
+                Ok((paths, encoding))
+            })
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// The encoding configured for `path`, if any.
+    pub fn for_path(&self, path: &Path) -> Option<&'static Encoding> {
+        let path = crate::file_source::strip_cur_dir(path);
+        self.rules
+            .iter()
+            .rev()
+            .find(|(paths, _)| paths.is_match(&path))
+            .map(|(_, encoding)| *encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_1252_offsets_and_round_trip() {
+        // "José Müller NIK 3171234567890123" in Windows-1252
+        let mut bytes = b"Jos\xE9 M\xFCller NIK ".to_vec();
+        bytes.extend_from_slice(b"3171234567890123");
+        let decoded = DecodedText::decode(&bytes, None).unwrap();
+        assert_eq!(decoded.encoding_name(), "windows-1252");
+        assert_eq!(decoded.text, "José Müller NIK 3171234567890123");
+
+        let start = decoded.text.find("3171").unwrap();
+        assert_eq!(start, 18);
+        assert_eq!(decoded.original_offset(start), 16);
+        assert_eq!(&bytes[decoded.original_offset(start)..][..4], b"3171");
+
+        let redacted = decoded.text.replace("3171234567890123", "XXXXXXXXXXXXXXXX");
+        let encoded = decoded.encode(&redacted);
+        assert_eq!(&encoded[..16], &bytes[..16]);
+        assert_eq!(&encoded[16..], b"XXXXXXXXXXXXXXXX");
+    }
+
+    #[test]
+    fn test_utf16_with_bom() {
+        let mut bytes = crate::content::UTF16LE_BOM.to_vec();
+        bytes.extend(
+            "Pasien: Siti\nSSN 123-45-6789"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        let decoded = DecodedText::decode(&bytes, None).unwrap();
+        assert_eq!(decoded.encoding_name(), "UTF-16LE");
+        let start = decoded.text.find("123").unwrap();
+        assert_eq!(decoded.original_offset(start), 2 + start * 2);
+        assert_eq!(decoded.encode(&decoded.text), bytes);
+
+        let utf8 = DecodedText::decode("\u{FEFF}héllo".as_bytes(), None).unwrap();
+        assert_eq!(utf8.text, "héllo");
+        assert_eq!(utf8.original_offset(3), 6);
+    }
+
+    #[test]
+    fn test_configured_encoding_and_rules() {
+        let rules = EncodingRules::new(&[
+            EncodingRule {
+                paths: vec!["exports/**".to_string()],
+                encoding: "latin1".to_string(),
+            },
+            EncodingRule {
+                paths: vec!["exports/ward/**".to_string()],
+                encoding: "utf-16be".to_string(),
+            },
+        ])
+        .unwrap();
+        assert_eq!(
+            rules.for_path(Path::new("./exports/a.csv")),
+            Some(encoding_rs::WINDOWS_1252)
+        );
+        assert_eq!(
+            rules.for_path(Path::new("exports/ward/b.csv")),
+            Some(UTF_16BE)
+        );
+        assert_eq!(rules.for_path(Path::new("notes.txt")), None);
+
+        // Valid UTF-8 is still decoded as configured.
+        let decoded = DecodedText::decode("é".as_bytes(), Some(encoding_rs::WINDOWS_1252)).unwrap();
+        assert_eq!(decoded.text, "Ã©");
+        assert!(DecodedText::decode(&[0xC3, 0x28], Some(UTF_8)).is_err());
+
+        let unknown = EncodingRule {
+            paths: vec!["**".to_string()],
+            encoding: "klingon".to_string(),
+        };
+        match EncodingRules::new(&[unknown]).unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("'klingon'")),
+            _ => panic!("Expected ConfigError"),
+        }
+    }
+}
@@ -12,8 +12,17 @@ pub enum HealthwandError {
     #[error("I/O error: {0}")]
     IoError(#[from] io::Error),
 
-    #[error("regex error: {0}")]
-    RegexError(#[from] regex::Error),
+    /// A regex failed to compile or exceeded a [`RegexLimits`](crate::domain::RegexLimits) limit.
+    #[error(
+        "regex error{}: {source}",
+        .pattern.as_ref().map(|id| format!(" in pattern '{}'", id)).unwrap_or_default()
+    )]
+    RegexError {
+        /// Catalogue ID of the pattern, when the regex came from the catalogue.
+        pattern: Option<String>,
+        #[source]
+        source: regex::Error,
+    },
 
     #[error("YAML error: {0}")]
     YamlError(String),
@@ -22,5 +31,14 @@ pub enum HealthwandError {
     UnsupportedDetector(String),
 }
 
+impl From<regex::Error> for HealthwandError {
+    fn from(source: regex::Error) -> Self {
+        HealthwandError::RegexError {
+            pattern: None,
+            source,
+        }
+    }
+}
+
 /// Convenience type alias for `Result<T, HealthwandError>`.
 pub type Result<T> = std::result::Result<T, HealthwandError>;
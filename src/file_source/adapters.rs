@@ -0,0 +1,256 @@
+//! Sources built from other sources; see [`FileSource::chain`],
+//! [`FileSource::filter`] and [`FileSource::map`].
+
+use super::{BlobOrigin, Entries, Entry, FileId, FileSource, FileSourceError, not_found};
+use crate::encoding::DecodedText;
+use std::ops::Range;
+use std::path::Path;
+
+/// The files of one source, then another.
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> Chain<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: FileSource, B: FileSource> FileSource for Chain<A, B> {
+    fn entries(&self) -> Entries<'_> {
+        Box::new(self.first.entries().chain(self.second.entries()))
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        match self.first.read_decoded(id) {
+            Err(e) if e.is_not_found() => self.second.read_decoded(id),
+            read => read,
+        }
+    }
+
+    fn reported_lines(&self, id: &FileId) -> Option<&[Range<u32>]> {
+        self.first
+            .reported_lines(id)
+            .or_else(|| self.second.reported_lines(id))
+    }
+
+    fn origin(&self, id: &FileId) -> Option<(&Path, &BlobOrigin)> {
+        self.first.origin(id).or_else(|| self.second.origin(id))
+    }
+}
+
+/// The files of a source whose ID satisfies a predicate.
+pub struct Filter<S, P> {
+    source: S,
+    predicate: P,
+}
+
+impl<S, P> Filter<S, P> {
+    pub fn new(source: S, predicate: P) -> Self {
+        Self { source, predicate }
+    }
+}
+
+impl<S: FileSource, P: Fn(&FileId) -> bool> FileSource for Filter<S, P> {
+    fn entries(&self) -> Entries<'_> {
+        Box::new(self.source.entries().filter(|entry| match entry {
+            Ok(Entry::File(id)) => (self.predicate)(id),
+            _ => true,
+        }))
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        if !(self.predicate)(id) {
+            return Err(not_found(id));
+        }
+        self.source.read_decoded(id)
+    }
+
+    fn reported_lines(&self, id: &FileId) -> Option<&[Range<u32>]> {
+        self.source.reported_lines(id)
+    }
+
+    fn origin(&self, id: &FileId) -> Option<(&Path, &BlobOrigin)> {
+        self.source.origin(id)
+    }
+}
+
+/// The files of a source, transformed after they are read.
+pub struct Map<S, F> {
+    source: S,
+    f: F,
+}
+
+impl<S, F> Map<S, F> {
+    pub fn new(source: S, f: F) -> Self {
+        Self { source, f }
+    }
+}
+
+impl<S: FileSource, F: Fn(&FileId, DecodedText) -> DecodedText> FileSource for Map<S, F> {
+    fn entries(&self) -> Entries<'_> {
+        self.source.entries()
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        self.source
+            .read_decoded(id)
+            .map(|decoded| (self.f)(id, decoded))
+    }
+
+    fn reported_lines(&self, id: &FileId) -> Option<&[Range<u32>]> {
+        self.source.reported_lines(id)
+    }
+
+    fn origin(&self, id: &FileId) -> Option<(&Path, &BlobOrigin)> {
+        self.source.origin(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_source::{MemoryFileSource, SkipReason};
+
+    /// One file with reported lines and an origin, as a git source would give.
+    struct Annotated {
+        files: MemoryFileSource,
+        lines: Vec<Range<u32>>,
+        path: std::path::PathBuf,
+        origin: BlobOrigin,
+    }
+
+    impl Annotated {
+        fn new() -> Self {
+            Self {
+                files: MemoryFileSource::new()
+                    .with_file("abc123:notes.txt", "nik 3171234567890123"),
+                lines: vec![2..4, 7..8],
+                path: "notes.txt".into(),
+                origin: BlobOrigin {
+                    blob: "b1".to_string(),
+                    commit: "abc123".to_string(),
+                    author_date: "2024-01-02T03:04:05+07:00".to_string(),
+                },
+            }
+        }
+    }
+
+    impl FileSource for Annotated {
+        fn entries(&self) -> Entries<'_> {
+            self.files.entries()
+        }
+
+        fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+            self.files.read_decoded(id)
+        }
+
+        fn reported_lines(&self, id: &FileId) -> Option<&[Range<u32>]> {
+            (id.as_path() == Path::new("abc123:notes.txt")).then_some(self.lines.as_slice())
+        }
+
+        fn origin(&self, id: &FileId) -> Option<(&Path, &BlobOrigin)> {
+            (id.as_path() == Path::new("abc123:notes.txt"))
+                .then_some((self.path.as_path(), &self.origin))
+        }
+    }
+
+    #[test]
+    fn test_chain_filter_map() {
+        let intake = MemoryFileSource::new()
+            .with_file("intake/1.txt", "nik 3171234567890123")
+            .with_file("intake/scan.bin", b"\0\x01\x02".to_vec());
+        let notes = MemoryFileSource::new()
+            .with_file("notes/a.md", "ssn 123-45-6789")
+            .with_file("notes/draft.md", "draft");
+        let source = intake
+            .chain(notes.filter(|id| !id.as_path().ends_with("draft.md")))
+            .map(|_, decoded| DecodedText::from(decoded.text.to_uppercase()));
+
+        let listing = source.listing().unwrap();
+        assert_eq!(
+            listing.files,
+            vec![FileId::from("intake/1.txt"), FileId::from("notes/a.md")]
+        );
+        assert_eq!(listing.skipped.len(), 1);
+        assert_eq!(listing.skipped[0].reason, SkipReason::Binary);
+        assert_eq!(
+            source.read_file(&"notes/a.md".into()).unwrap(),
+            "SSN 123-45-6789"
+        );
+        assert_eq!(
+            source.read_file(&"intake/1.txt".into()).unwrap(),
+            "NIK 3171234567890123"
+        );
+        assert!(
+            source
+                .read_file(&"notes/draft.md".into())
+                .unwrap_err()
+                .is_not_found()
+        );
+    }
+
+    #[test]
+    fn test_chain_falls_back_only_when_not_found() {
+        let first = MemoryFileSource::new()
+            .with_file("shared", b"\0\x01\x02".to_vec())
+            .with_file("first.txt", "one");
+        let second = MemoryFileSource::new()
+            .with_file("shared", "text")
+            .with_file("second.txt", "two");
+        let source = first.chain(second);
+        assert_eq!(source.read_file(&"first.txt".into()).unwrap(), "one");
+        assert_eq!(source.read_file(&"second.txt".into()).unwrap(), "two");
+        // The first source has the file but cannot read it: its error stands.
+        assert!(matches!(
+            source.read_file(&"shared".into()),
+            Err(FileSourceError::NotTextFile(_))
+        ));
+        assert!(
+            source
+                .read_file(&"missing".into())
+                .unwrap_err()
+                .is_not_found()
+        );
+    }
+
+    #[test]
+    fn test_filter_rejects_ids_failing_the_predicate() {
+        let source = MemoryFileSource::new()
+            .with_file("keep.txt", "kept")
+            .with_file("drop.txt", "dropped")
+            .filter(|id| id.as_path() != Path::new("drop.txt"));
+        assert_eq!(
+            source.listing().unwrap().files,
+            vec![FileId::from("keep.txt")]
+        );
+        assert_eq!(source.read_file(&"keep.txt".into()).unwrap(), "kept");
+        // Present in the inner source, but filtered out.
+        assert!(
+            source
+                .read_file(&"drop.txt".into())
+                .unwrap_err()
+                .is_not_found()
+        );
+    }
+
+    #[test]
+    fn test_map_passes_lines_and_origin_through() {
+        let inner = Annotated::new();
+        let source =
+            Annotated::new().map(|_, decoded| DecodedText::from(decoded.text.to_uppercase()));
+        let id = FileId::from("abc123:notes.txt");
+        assert_eq!(source.read_file(&id).unwrap(), "NIK 3171234567890123");
+        assert_eq!(source.reported_lines(&id), inner.reported_lines(&id));
+        assert_eq!(source.reported_lines(&id), Some(&[2..4, 7..8][..]));
+        assert_eq!(
+            source.origin(&id),
+            Some((Path::new("notes.txt"), &inner.origin))
+        );
+        let other = FileId::from("other.txt");
+        assert_eq!(source.reported_lines(&other), None);
+        assert_eq!(source.origin(&other), None);
+    }
+}
@@ -0,0 +1,578 @@
+//! Files inside zip, tar and gzip archives.
+//!
+//! [`ArchiveFileSource`] lists the files of a [`LocalFileSource`] and, for every
+//! archive among them, the files inside it as virtual paths such as
+//! `export.zip!/patients/2024.csv`. Archives inside archives are expanded up to
+//! [`ArchiveLimits::max_depth`]. Entries go through the same exclude/include globs,
+//! size limit, extractors and sniffing as files on disk; archives themselves are
+//! only subject to `exclude`, so `--include '**/*.csv'` still finds CSVs in a zip.
+//!
+//! Archive headers are not trusted: entries are read through a limit, and expanding
+//! one archive (with everything nested in it) stops after
+//! [`ArchiveLimits::max_total_size`] decompressed bytes, so a zip bomb costs at most
+//! that much memory and time.
+//!
+//! Each archive is expanded once, while it is listed; its entries' contents are
+//! held until they have been read.
+
+use super::{
+    Entries, Entry, FileId, FileSource, FileSourceError, LocalFileSource, SkipReason, SkippedFile,
+};
+use crate::content::sniff;
+use crate::encoding::DecodedText;
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Separator between an archive's path and the path of an entry inside it.
+pub const ARCHIVE_SEPARATOR: &str = "!/";
+
+/// Default for [`ArchiveLimits::max_total_size`].
+pub const DEFAULT_ARCHIVE_MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Archive formats, recognised by extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    /// `.tar.gz` or `.tgz`.
+    TarGz,
+    /// A single gzip-compressed file; its entry is the file name without `.gz`.
+    Gz,
+}
+
+impl ArchiveKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".gz") {
+            Some(ArchiveKind::Gz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Limits on expanding one archive on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveLimits {
+    /// Archives nested deeper than this are skipped; 1 expands archives on disk but
+    /// not archives inside them.
+    pub max_depth: usize,
+    /// Decompressed bytes read from one archive, including nested archives.
+    pub max_total_size: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            max_total_size: DEFAULT_ARCHIVE_MAX_SIZE,
+        }
+    }
+}
+
+/// Content of the files found in an archive, by virtual path.
+type EntryContents = BTreeMap<PathBuf, Vec<u8>>;
+
+/// The files found in one archive on disk.
+#[derive(Debug, Default)]
+struct Expansion {
+    files: EntryContents,
+    /// Paths in the order they were found.
+    order: Vec<PathBuf>,
+    skipped: Vec<SkippedFile>,
+    /// Decompressed bytes that may still be read.
+    budget: u64,
+    exhausted: bool,
+}
+
+/// A [`LocalFileSource`] that also yields the files inside archives.
+pub struct ArchiveFileSource {
+    inner: LocalFileSource,
+    limits: ArchiveLimits,
+    /// Contents of listed archives, by archive, until each entry has been read.
+    pending: Mutex<HashMap<PathBuf, EntryContents>>,
+}
+
+impl ArchiveFileSource {
+    pub fn new(inner: LocalFileSource, limits: ArchiveLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Read the archive at `path` and everything nested in it.
+    fn expand(&self, path: &Path) -> io::Result<Expansion> {
+        let mut expansion = Expansion {
+            budget: self.limits.max_total_size,
+            ..Default::default()
+        };
+        let kind = ArchiveKind::from_path(path).expect("only archives are expanded");
+        self.expand_reader(path, kind, File::open(path)?, 1, &mut expansion)?;
+        if expansion.exhausted {
+            warn!(
+                "Stopped reading {}: more than {} bytes decompressed",
+                path.display(),
+                self.limits.max_total_size
+            );
+            expansion.skipped.push(SkippedFile {
+                id: path.to_path_buf().into(),
+                reason: SkipReason::ArchiveLimit,
+            });
+        }
+        Ok(expansion)
+    }
+
+    fn expand_reader<R: Read + Seek>(
+        &self,
+        archive: &Path,
+        kind: ArchiveKind,
+        reader: R,
+        depth: usize,
+        expansion: &mut Expansion,
+    ) -> io::Result<()> {
+        match kind {
+            ArchiveKind::Zip => {
+                let mut zip = zip::ZipArchive::new(reader).map_err(io::Error::other)?;
+                for i in 0..zip.len() {
+                    if expansion.exhausted {
+                        break;
+                    }
+                    let name = zip.name_for_index(i).unwrap_or_default().to_string();
+                    let mut file = match zip.by_index(i) {
+                        Ok(file) => file,
+                        Err(e) => {
+                            warn!("Skipping {}!/{}: {}", archive.display(), name, e);
+                            expansion.skipped.push(SkippedFile {
+                                id: entry_path(archive, Path::new(&name)).into(),
+                                reason: SkipReason::Unreadable,
+                            });
+                            continue;
+                        }
+                    };
+                    if !file.is_file() {
+                        continue;
+                    }
+                    let Some(name) = file.enclosed_name() else {
+                        expansion.skipped.push(SkippedFile {
+                            id: entry_path(archive, Path::new(&name)).into(),
+                            reason: SkipReason::Unreadable,
+                        });
+                        continue;
+                    };
+                    self.entry(archive, &name, &mut file, depth, expansion)?;
+                }
+            }
+            ArchiveKind::Tar => self.expand_tar(archive, reader, depth, expansion)?,
+            ArchiveKind::TarGz => {
+                self.expand_tar(archive, GzDecoder::new(reader), depth, expansion)?
+            }
+            ArchiveKind::Gz => {
+                let name = archive.file_stem().map(PathBuf::from).unwrap_or_default();
+                self.entry(
+                    archive,
+                    &name,
+                    &mut GzDecoder::new(reader),
+                    depth,
+                    expansion,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn expand_tar<R: Read>(
+        &self,
+        archive: &Path,
+        reader: R,
+        depth: usize,
+        expansion: &mut Expansion,
+    ) -> io::Result<()> {
+        let mut tar = tar::Archive::new(reader);
+        for entry in tar.entries()? {
+            if expansion.exhausted {
+                break;
+            }
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = entry.path()?.into_owned();
+            if name
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+            {
+                expansion.skipped.push(SkippedFile {
+                    id: entry_path(archive, &name).into(),
+                    reason: SkipReason::Unreadable,
+                });
+                continue;
+            }
+            self.entry(archive, &name, &mut entry, depth, expansion)?;
+        }
+        Ok(())
+    }
+
+    /// Read the entry `name` of `archive` from `reader` and list, skip or expand it.
+    fn entry(
+        &self,
+        archive: &Path,
+        name: &Path,
+        reader: &mut dyn Read,
+        depth: usize,
+        expansion: &mut Expansion,
+    ) -> io::Result<()> {
+        let path = entry_path(archive, name);
+        let skip = |expansion: &mut Expansion, path: PathBuf, reason| {
+            expansion.skipped.push(SkippedFile {
+                id: path.into(),
+                reason,
+            });
+        };
+        if self.excluded(&path) {
+            skip(expansion, path, SkipReason::Excluded);
+            return Ok(());
+        }
+
+        let max_file_size = self.inner.max_file_size.unwrap_or(u64::MAX);
+        let limit = max_file_size.min(expansion.budget);
+        let mut bytes = Vec::new();
+        reader
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut bytes)?;
+        let len = bytes.len() as u64;
+        expansion.budget = expansion.budget.saturating_sub(len);
+        if len > limit {
+            if len > max_file_size {
+                skip(expansion, path, SkipReason::TooLarge);
+            } else {
+                expansion.exhausted = true;
+            }
+            return Ok(());
+        }
+
+        if let Some(kind) = ArchiveKind::from_path(&path) {
+            if depth >= self.limits.max_depth {
+                skip(expansion, path, SkipReason::ArchiveDepth);
+                return Ok(());
+            }
+            if let Err(e) =
+                self.expand_reader(&path, kind, Cursor::new(bytes), depth + 1, expansion)
+            {
+                warn!("Skipping {}: {}", path.display(), e);
+                skip(expansion, path, SkipReason::Unreadable);
+            }
+            return Ok(());
+        }
+        match self.inner.skip_reason(&path, len, || Some(sniff(&bytes))) {
+            Some(reason) => skip(expansion, path, reason),
+            None => {
+                expansion.order.push(path.clone());
+                expansion.files.insert(path, bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `path` or a directory above it matches an exclude glob.
+    fn excluded(&self, path: &Path) -> bool {
+        path.ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .any(|p| self.inner.exclude.is_match(self.inner.glob_path(p)))
+    }
+
+    /// Content of the archive entry `path`, taken from the expansion made when its
+    /// archive was listed. An entry read a second time, or never listed, costs a
+    /// fresh expansion of its archive.
+    fn entry_bytes(&self, path: &Path) -> Result<Vec<u8>, FileSourceError> {
+        let archive = outer_archive(path).ok_or_else(|| {
+            FileSourceError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "not an archive entry",
+            ))
+        })?;
+        {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(files) = pending.get_mut(&archive) {
+                if let Some(bytes) = files.remove(path) {
+                    if files.is_empty() {
+                        pending.remove(&archive);
+                    }
+                    return Ok(bytes);
+                }
+            }
+        }
+        self.expand(&archive)?
+            .files
+            .remove(path)
+            .ok_or_else(|| FileSourceError::NotTextFile(path.to_path_buf()))
+    }
+}
+
+impl ArchiveFileSource {
+    /// The entries of the archive at `path`: its files, then the files it skipped.
+    /// The files' contents are kept for [`FileSource::read_decoded`].
+    fn archive_entries(&self, archive: &Path) -> Vec<Result<Entry, FileSourceError>> {
+        match self.expand(archive) {
+            Ok(expansion) => {
+                if !expansion.files.is_empty() {
+                    self.pending
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(archive.to_path_buf(), expansion.files);
+                }
+                expansion
+                    .order
+                    .into_iter()
+                    .map(|path| Entry::File(path.into()))
+                    .chain(expansion.skipped.into_iter().map(Entry::Skipped))
+                    .map(Ok)
+                    .collect()
+            }
+            Err(e) => {
+                warn!("Skipping {}: {}", archive.display(), e);
+                vec![Ok(Entry::Skipped(SkippedFile {
+                    id: archive.into(),
+                    reason: SkipReason::Unreadable,
+                }))]
+            }
+        }
+    }
+}
+
+impl FileSource for ArchiveFileSource {
+    fn entries(&self) -> Entries<'_> {
+        let is_archive = |id: &FileId| ArchiveKind::from_path(id.as_path()).is_some();
+        Box::new(self.inner.entries().flat_map(move |entry| match entry {
+            Ok(Entry::File(id)) if is_archive(&id) => self.archive_entries(id.as_path()),
+            // Archives are not subject to include globs, sniffing or the file size limit.
+            Ok(Entry::Skipped(skipped))
+                if is_archive(&skipped.id)
+                    && matches!(
+                        skipped.reason,
+                        SkipReason::NotIncluded
+                            | SkipReason::TooLarge
+                            | SkipReason::Extension
+                            | SkipReason::Binary
+                    ) =>
+            {
+                self.archive_entries(skipped.id.as_path())
+            }
+            entry => vec![entry],
+        }))
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        let path = id.as_path();
+        if outer_archive(path).is_none() {
+            return self.inner.read_decoded(id);
+        }
+        let bytes = self.entry_bytes(path)?;
+        self.inner.decode(path, bytes)
+    }
+}
+
+/// `name` inside `archive`, e.g. `export.zip!/patients/2024.csv`.
+fn entry_path(archive: &Path, name: &Path) -> PathBuf {
+    PathBuf::from(format!(
+        "{}{}{}",
+        archive.display(),
+        ARCHIVE_SEPARATOR,
+        name.display()
+    ))
+}
+
+/// The archive on disk containing the entry `path`, if `path` is an entry.
+fn outer_archive(path: &Path) -> Option<PathBuf> {
+    let text = path.to_str()?;
+    text.match_indices(ARCHIVE_SEPARATOR)
+        .map(|(i, _)| PathBuf::from(&text[..i]))
+        .find(|archive| ArchiveKind::from_path(archive).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+
+    fn zip_bytes(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn tar_gz_bytes(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, *content).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn relative(root: &Path, id: &FileId) -> String {
+        id.as_path()
+            .strip_prefix(root)
+            .unwrap()
+            .display()
+            .to_string()
+    }
+
+    #[test]
+    fn test_nested_archives_are_listed_and_read() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let inner = zip_bytes(&[
+            ("patients/2024.csv", b"nik,3171234567890123\n"),
+            ("scan.dcm", b"DICM\0\0\x01"),
+        ]);
+        let bundle = tar_gz_bytes(&[("exports/export.zip", &inner), ("README.md", b"hi")]);
+        std::fs::write(root.join("bundle.tar.gz"), bundle).unwrap();
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(b"SSN 123-45-6789").unwrap();
+        std::fs::write(root.join("notes.txt.gz"), gz.finish().unwrap()).unwrap();
+
+        let source = ArchiveFileSource::new(
+            LocalFileSource::new(root, Vec::new()),
+            ArchiveLimits::default(),
+        );
+        let listing = source.listing().unwrap();
+        let mut files: Vec<String> = listing.files.iter().map(|f| relative(root, f)).collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "bundle.tar.gz!/README.md",
+                "bundle.tar.gz!/exports/export.zip!/patients/2024.csv",
+                "notes.txt.gz!/notes.txt",
+            ]
+        );
+        assert_eq!(listing.skipped.len(), 1);
+        assert_eq!(listing.skipped[0].reason, SkipReason::Binary);
+
+        let csv = root.join("bundle.tar.gz!/exports/export.zip!/patients/2024.csv");
+        assert_eq!(
+            source.read_file(&csv.into()).unwrap(),
+            "nik,3171234567890123\n"
+        );
+        let notes = root.join("notes.txt.gz!/notes.txt");
+        assert_eq!(source.read_file(&notes.into()).unwrap(), "SSN 123-45-6789");
+    }
+
+    #[test]
+    fn test_entries_are_read_from_the_listing_expansion() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("a.zip"),
+            zip_bytes(&[("one.txt", b"one"), ("two.txt", b"two")]),
+        )
+        .unwrap();
+        std::fs::write(root.join("b.zip"), zip_bytes(&[("three.txt", b"three")])).unwrap();
+
+        let source = ArchiveFileSource::new(
+            LocalFileSource::new(root, Vec::new()),
+            ArchiveLimits::default(),
+        );
+        let listing = source.listing().unwrap();
+        assert_eq!(listing.files.len(), 3);
+        // With the archives gone, reads can only be served from the listing.
+        std::fs::remove_file(root.join("a.zip")).unwrap();
+        std::fs::remove_file(root.join("b.zip")).unwrap();
+        let mut contents: Vec<String> = listing
+            .files
+            .iter()
+            .map(|f| source.read_file(f).unwrap())
+            .collect();
+        contents.sort();
+        assert_eq!(contents, vec!["one", "three", "two"]);
+        // Every entry has been handed out, so nothing is held any more.
+        assert!(source.pending.lock().unwrap().is_empty());
+        assert!(source.read_file(&listing.files[0]).is_err());
+    }
+
+    #[test]
+    fn test_limits_and_globs_apply_inside_archives() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let nested = zip_bytes(&[("deep.txt", b"deep")]);
+        let archive = zip_bytes(&[
+            ("a.txt", b"a"),
+            ("big.txt", &[b'x'; 2048]),
+            ("vendor/lib.txt", b"v"),
+            ("nested.zip", &nested),
+        ]);
+        std::fs::write(root.join("export.zip"), archive).unwrap();
+
+        let local = || {
+            LocalFileSource::new(root, Vec::new())
+                .with_exclude(&["**/vendor".to_string()])
+                .unwrap()
+                .with_max_file_size(Some(1024))
+        };
+        let limits = ArchiveLimits {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let listing = ArchiveFileSource::new(local(), limits).listing().unwrap();
+        assert_eq!(
+            listing.files,
+            vec![FileId::from(root.join("export.zip!/a.txt"))]
+        );
+        let mut skipped: Vec<(String, SkipReason)> = listing
+            .skipped
+            .iter()
+            .map(|s| (relative(root, &s.id), s.reason))
+            .collect();
+        skipped.sort();
+        assert_eq!(
+            skipped,
+            vec![
+                ("export.zip!/big.txt".to_string(), SkipReason::TooLarge),
+                (
+                    "export.zip!/nested.zip".to_string(),
+                    SkipReason::ArchiveDepth
+                ),
+                (
+                    "export.zip!/vendor/lib.txt".to_string(),
+                    SkipReason::Excluded
+                ),
+            ]
+        );
+
+        let bomb = ArchiveLimits {
+            max_depth: 3,
+            max_total_size: 512,
+        };
+        let listing = ArchiveFileSource::new(local(), bomb).listing().unwrap();
+        assert!(
+            listing
+                .skipped
+                .iter()
+                .any(|s| s.reason == SkipReason::ArchiveLimit
+                    && s.id.as_path() == root.join("export.zip"))
+        );
+    }
+}
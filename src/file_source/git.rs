@@ -0,0 +1,430 @@
+//! Lines added by a change in a local git repository.
+//!
+//! [`GitDiffSource`] lists the files a change adds lines to, reads their post-change
+//! content (from the index, a commit or the working tree), and reports findings only
+//! on the added lines, via [`FileSource::reported_lines`]. Whole files are still
+//! scanned, so context words on unchanged lines count.
+//!
+//! The `git` executable is run against the repository; nothing is fetched.
+
+use super::{
+    Entries, Entry, FileId, FileSource, FileSourceError, LocalFileSource, SkippedFile, not_found,
+};
+use crate::content::sniff;
+use crate::encoding::DecodedText;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+/// Object ID of the empty tree, to diff against in a repository with no commits.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Added lines of an untracked file.
+const WHOLE_FILE: Range<u32> = 1..u32::MAX;
+
+/// Which change to scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitScope {
+    /// Staged changes: the index against `HEAD`.
+    Staged,
+    /// Uncommitted changes, staged or not, and untracked files (which are scanned
+    /// whole): the working tree against `HEAD`.
+    Worktree,
+    /// Commits in `base..head`, or with `symmetric` in `base...head`: the changes on
+    /// `head` since it forked from `base`, whatever `base` gained since.
+    Range {
+        base: String,
+        head: String,
+        symmetric: bool,
+    },
+}
+
+impl FromStr for GitScope {
+    type Err = String;
+
+    /// Parse a `base..head` or `base...head` range; an empty side means `HEAD`.
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let (base, head, symmetric) = match range.split_once("...") {
+            Some((base, head)) => (base, head, true),
+            None => range
+                .split_once("..")
+                .map(|(base, head)| (base, head, false))
+                .ok_or_else(|| format!("expected <base>..<head>, got '{}'", range))?,
+        };
+        let rev = |r: &str| if r.is_empty() { "HEAD" } else { r }.to_string();
+        Ok(GitScope::Range {
+            base: rev(base),
+            head: rev(head),
+            symmetric,
+        })
+    }
+}
+
+/// A file the change adds lines to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedFile {
+    /// Post-change path, relative to the scanned directory.
+    pub path: PathBuf,
+    /// Added lines, 1-based, as half-open ranges.
+    pub added: Vec<Range<u32>>,
+}
+
+/// The files changed in a local repository, read as of the change.
+///
+/// Paths are the scanned directory joined with the path git reports, so globs and
+/// rules match them as they would in a directory scan. Only changes under the
+/// scanned directory are listed.
+pub struct GitDiffSource {
+    inner: LocalFileSource,
+    scope: GitScope,
+    changed: Vec<ChangedFile>,
+}
+
+impl GitDiffSource {
+    /// The change `scope` in the repository containing `inner.root`. `inner`'s globs,
+    /// size limit, file types and encodings apply to the changed files.
+    pub fn new(inner: LocalFileSource, scope: GitScope) -> io::Result<Self> {
+        let repo = &inner.root;
+        let mut args = vec![
+            "diff",
+            "--no-color",
+            "--no-ext-diff",
+            "--no-textconv",
+            "--relative",
+            "--unified=0",
+            "--diff-filter=ACMRT",
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+        ];
+        let has_head = git(repo, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok();
+        let merge_base_range;
+        match &scope {
+            GitScope::Staged => args.push("--cached"),
+            GitScope::Worktree => args.push(if has_head { "HEAD" } else { EMPTY_TREE }),
+            // `git diff base...head` diffs `head` against the merge base.
+            GitScope::Range {
+                base,
+                head,
+                symmetric: true,
+            } => {
+                merge_base_range = format!("{}...{}", base, head);
+                args.push(&merge_base_range);
+            }
+            GitScope::Range { base, head, .. } => args.extend([base.as_str(), head.as_str()]),
+        }
+        let mut changed = parse_diff(&git(repo, &args)?);
+        if scope == GitScope::Worktree {
+            let untracked = git(repo, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+            changed.extend(
+                untracked
+                    .split(|&b| b == 0)
+                    .filter(|path| !path.is_empty())
+                    .map(|path| ChangedFile {
+                        path: PathBuf::from(String::from_utf8_lossy(path).into_owned()),
+                        added: vec![WHOLE_FILE],
+                    }),
+            );
+        }
+        Ok(Self {
+            inner,
+            scope,
+            changed,
+        })
+    }
+
+    /// The changed files, with paths relative to the scanned directory.
+    pub fn changed_files(&self) -> &[ChangedFile] {
+        &self.changed
+    }
+
+    fn changed_file(&self, path: &Path) -> Option<&ChangedFile> {
+        let relative = path.strip_prefix(&self.inner.root).ok()?;
+        self.changed.iter().find(|c| c.path == relative)
+    }
+
+    /// Post-change content of `relative`.
+    fn read_bytes(&self, relative: &Path) -> io::Result<Vec<u8>> {
+        let spec = |rev: &str| format!("{}:./{}", rev, relative.to_string_lossy());
+        match &self.scope {
+            GitScope::Staged => git(&self.inner.root, &["show", &spec("")]),
+            GitScope::Range { head, .. } => git(&self.inner.root, &["show", &spec(head)]),
+            GitScope::Worktree => fs::read(self.inner.root.join(relative)),
+        }
+    }
+}
+
+impl FileSource for GitDiffSource {
+    fn entries(&self) -> Entries<'_> {
+        Box::new(self.changed.iter().map(|changed| {
+            let path = self.inner.root.join(&changed.path);
+            let bytes = self.read_bytes(&changed.path)?;
+            Ok(
+                match self
+                    .inner
+                    .skip_reason(&path, bytes.len() as u64, || Some(sniff(&bytes)))
+                {
+                    Some(reason) => Entry::Skipped(SkippedFile {
+                        id: path.into(),
+                        reason,
+                    }),
+                    None => Entry::File(path.into()),
+                },
+            )
+        }))
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        let changed = self
+            .changed_file(id.as_path())
+            .ok_or_else(|| not_found(id))?;
+        let bytes = self.read_bytes(&changed.path)?;
+        self.inner.decode(id.as_path(), bytes)
+    }
+
+    fn reported_lines(&self, id: &FileId) -> Option<&[Range<u32>]> {
+        self.changed_file(id.as_path())
+            .map(|changed| changed.added.as_slice())
+    }
+}
+
+/// Run git in `repo` and return its output; a failure carries git's message.
+pub(super) fn git(repo: &Path, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "core.quotePath=false"])
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Added lines per file from `git diff --unified=0` output.
+fn parse_diff(diff: &[u8]) -> Vec<ChangedFile> {
+    let mut changed: Vec<ChangedFile> = Vec::new();
+    let mut current: Option<ChangedFile> = None;
+    // Between `diff --git` and the first hunk; an added line can start with `++ `.
+    let mut in_header = false;
+    for line in diff.split(|&b| b == b'\n') {
+        let line = String::from_utf8_lossy(line);
+        if line.starts_with("diff --git ") {
+            changed.extend(current.take().filter(|c| !c.added.is_empty()));
+            in_header = true;
+        } else if let Some(path) = line.strip_prefix("+++ ").filter(|_| in_header) {
+            let path = unquote(path);
+            current = (path != "/dev/null").then(|| ChangedFile {
+                path: PathBuf::from(path.strip_prefix("b/").unwrap_or(&path)),
+                added: Vec::new(),
+            });
+        } else if let (Some(file), Some(hunk)) = (current.as_mut(), line.strip_prefix("@@ ")) {
+            in_header = false;
+            // @@ -old[,len] +new[,len] @@
+            let new = hunk.split(' ').find_map(|r| r.strip_prefix('+'));
+            let (start, len) = match new.map(|r| r.split_once(',').unwrap_or((r, "1"))) {
+                Some((start, len)) => (start.parse().unwrap_or(0), len.parse().unwrap_or(0)),
+                None => (0, 0),
+            };
+            if len > 0 {
+                file.added.push(start..start + len);
+            }
+        }
+    }
+    changed.extend(current.filter(|c| !c.added.is_empty()));
+    changed
+}
+
+/// A path as git prints it, undoing the C-style quoting of unusual names.
+pub(super) fn unquote(path: &str) -> String {
+    let Some(quoted) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut unquoted = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        unquoted.push(match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some(other) => other,
+                None => break,
+            },
+            (c, false) => c,
+        });
+    }
+    unquoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(repo: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    #[test]
+    fn test_parse_diff_and_scope() {
+        let diff = b"diff --git a/notes.txt b/notes.txt\n\
+            --- a/notes.txt\n\
+            +++ b/notes.txt\n\
+            @@ -3 +3,2 @@ intro\n\
+            -old\n\
+            +NIK 3171234567890123\n\
+            +++ SSN 123-45-6789\n\
+            @@ -9,2 +10,0 @@\n\
+            -gone\n\
+            diff --git a/\"dir/pasien \\\"a\\\".csv\" b/\"dir/pasien \\\"a\\\".csv\"\n\
+            +++ \"b/dir/pasien \\\"a\\\".csv\"\n\
+            @@ -0,0 +1 @@\n\
+            +x\n\
+            diff --git a/only-removed.txt b/only-removed.txt\n\
+            +++ b/only-removed.txt\n\
+            @@ -1 +0,0 @@\n";
+        let changed = parse_diff(diff);
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed[0].path, PathBuf::from("notes.txt"));
+        assert_eq!(changed[0].added, vec![3..5]);
+        assert_eq!(changed[1].path, PathBuf::from("dir/pasien \"a\".csv"));
+        assert_eq!(changed[1].added, vec![1..2]);
+
+        assert_eq!(
+            "main..feature".parse(),
+            Ok(GitScope::Range {
+                base: "main".to_string(),
+                head: "feature".to_string(),
+                symmetric: false
+            })
+        );
+        assert_eq!(
+            "origin/main...".parse::<GitScope>().unwrap(),
+            GitScope::Range {
+                base: "origin/main".to_string(),
+                head: "HEAD".to_string(),
+                symmetric: true
+            }
+        );
+        assert!("main".parse::<GitScope>().is_err());
+    }
+
+    #[test]
+    fn test_staged_range_and_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        run(repo, &["init", "-q"]);
+        fs::write(repo.join("a.txt"), "line one\nline two\n").unwrap();
+        run(repo, &["add", "a.txt"]);
+        run(repo, &["commit", "-q", "-m", "first"]);
+
+        fs::write(
+            repo.join("a.txt"),
+            "line one\nNIK 3171234567890123\nline two\n",
+        )
+        .unwrap();
+        run(repo, &["add", "a.txt"]);
+        fs::write(
+            repo.join("a.txt"),
+            "unstaged\nline one\nNIK 3171234567890123\nline two\n",
+        )
+        .unwrap();
+        fs::write(repo.join("new.txt"), "SSN 123-45-6789\n").unwrap();
+
+        let lines = |source: &GitDiffSource, id: &FileId| -> Vec<(u32, u32)> {
+            let ranges = source.reported_lines(id).unwrap();
+            ranges.iter().map(|r| (r.start, r.end)).collect()
+        };
+        let source = |scope| GitDiffSource::new(LocalFileSource::new(repo, vec![]), scope).unwrap();
+        let staged = source(GitScope::Staged);
+        let a = FileId::from(repo.join("a.txt"));
+        let new = FileId::from(repo.join("new.txt"));
+        assert_eq!(staged.listing().unwrap().files, vec![a.clone()]);
+        assert_eq!(lines(&staged, &a), vec![(2, 3)]);
+        assert_eq!(
+            staged.read_file(&a).unwrap(),
+            "line one\nNIK 3171234567890123\nline two\n"
+        );
+
+        let worktree = source(GitScope::Worktree);
+        assert_eq!(
+            worktree.listing().unwrap().files,
+            vec![a.clone(), new.clone()]
+        );
+        assert_eq!(lines(&worktree, &a), vec![(1, 2), (3, 4)]);
+
+        run(repo, &["commit", "-q", "-m", "second"]);
+        let range = source("HEAD~1..HEAD".parse().unwrap());
+        assert_eq!(lines(&range, &a), vec![(2, 3)]);
+        assert_eq!(range.reported_lines(&new), None);
+        assert!(range.read_file(&new).unwrap_err().is_not_found());
+        assert!(range.read_file(&a).unwrap().starts_with("line one\nNIK"));
+
+        assert!(
+            GitDiffSource::new(
+                LocalFileSource::new(repo, vec![]),
+                "nope..HEAD".parse().unwrap()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_symmetric_range_diffs_from_merge_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        run(repo, &["init", "-q", "-b", "main"]);
+        fs::write(repo.join("a.txt"), "line one\nSSN 123-45-6789\n").unwrap();
+        run(repo, &["add", "a.txt"]);
+        run(repo, &["commit", "-q", "-m", "first"]);
+
+        run(repo, &["checkout", "-q", "-b", "feature"]);
+        fs::write(repo.join("b.txt"), "NIK 3171234567890123\n").unwrap();
+        run(repo, &["add", "b.txt"]);
+        run(repo, &["commit", "-q", "-m", "feature"]);
+
+        // main moves on after the fork, removing a line the feature branch still has.
+        run(repo, &["checkout", "-q", "main"]);
+        fs::write(repo.join("a.txt"), "line one\n").unwrap();
+        run(repo, &["commit", "-q", "-am", "main"]);
+
+        let changed = |range: &str| -> Vec<(String, Vec<(u32, u32)>)> {
+            let source =
+                GitDiffSource::new(LocalFileSource::new(repo, vec![]), range.parse().unwrap())
+                    .unwrap();
+            source
+                .changed_files()
+                .iter()
+                .map(|f| {
+                    let added = f.added.iter().map(|r| (r.start, r.end)).collect();
+                    (f.path.display().to_string(), added)
+                })
+                .collect()
+        };
+        // Comparing the tips blames the feature branch for the line main removed.
+        assert_eq!(
+            changed("main..feature"),
+            vec![
+                ("a.txt".to_string(), vec![(2, 3)]),
+                ("b.txt".to_string(), vec![(1, 2)]),
+            ]
+        );
+        assert_eq!(
+            changed("main...feature"),
+            vec![("b.txt".to_string(), vec![(1, 2)])]
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! Every file version ever committed to a local git repository.
+//!
+//! [`GitHistorySource`] walks the commits reachable from any ref, oldest first, and
+//! lists each blob (file version) once, however many commits and paths share it.
+//! A blob is identified as `<commit>:<path>` of the first commit that added it;
+//! [`FileSource::origin`] gives its path, commit, author date and object ID, so a
+//! patient export deleted long ago is still reported and can be purged by blob ID.
+
+use super::git::{git, unquote};
+use super::{
+    Entries, Entry, FileId, FileSource, FileSourceError, LocalFileSource, SkippedFile, not_found,
+};
+use crate::content::sniff;
+use crate::encoding::DecodedText;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+
+/// Where a blob from a history scan was first committed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobOrigin {
+    /// Object ID of the blob, as passed to `git filter-repo --strip-blobs-with-ids`.
+    pub blob: String,
+    /// The first commit (oldest by commit date) that added the blob.
+    pub commit: String,
+    /// That commit's author date, in strict ISO 8601.
+    pub author_date: String,
+}
+
+/// One blob of the history, at the path and commit it was first seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HistoryBlob {
+    /// `<commit>:<path>`, unique per blob.
+    id: FileId,
+    /// Scanned directory joined with the blob's path.
+    path: PathBuf,
+    origin: BlobOrigin,
+}
+
+/// Every blob reachable from any ref of the repository containing the scanned
+/// directory, read with `git cat-file`.
+pub struct GitHistorySource {
+    inner: LocalFileSource,
+    blobs: Vec<HistoryBlob>,
+    /// Index into `blobs` by ID.
+    ids: HashMap<FileId, usize>,
+    /// `git cat-file --batch-check`, for blob sizes.
+    cat_file_check: Mutex<Option<CatFile>>,
+    /// `git cat-file --batch`, for blob contents.
+    cat_file: Mutex<Option<CatFile>>,
+}
+
+impl GitHistorySource {
+    /// The history of the repository containing `inner.root`, limited to paths under
+    /// it. `inner`'s globs, size limit, file types and encodings apply to each blob.
+    pub fn new(inner: LocalFileSource) -> io::Result<Self> {
+        let log = git(
+            &inner.root,
+            &[
+                "log",
+                "--all",
+                "--reverse",
+                "--no-renames",
+                "--root",
+                "-m",
+                "--raw",
+                "--no-abbrev",
+                "--relative",
+                "--format=commit %H %aI",
+            ],
+        )?;
+        let blobs: Vec<HistoryBlob> = parse_log(&log)
+            .into_iter()
+            .map(|(path, origin)| HistoryBlob {
+                id: FileId::from(format!("{}:{}", origin.commit, path)),
+                path: inner.root.join(path),
+                origin,
+            })
+            .collect();
+        let ids = blobs
+            .iter()
+            .enumerate()
+            .map(|(i, blob)| (blob.id.clone(), i))
+            .collect();
+        Ok(Self {
+            inner,
+            blobs,
+            ids,
+            cat_file_check: Mutex::new(None),
+            cat_file: Mutex::new(None),
+        })
+    }
+
+    fn blob(&self, id: &FileId) -> Option<&HistoryBlob> {
+        self.ids.get(id).map(|&i| &self.blobs[i])
+    }
+
+    fn blob_size(&self, oid: &str) -> io::Result<u64> {
+        with_cat_file(
+            &self.cat_file_check,
+            &self.inner.root,
+            "--batch-check",
+            |c| c.header(oid).map(|size| size as u64),
+        )
+    }
+
+    fn read_blob(&self, oid: &str) -> io::Result<Vec<u8>> {
+        with_cat_file(&self.cat_file, &self.inner.root, "--batch", |c| c.read(oid))
+    }
+}
+
+impl FileSource for GitHistorySource {
+    fn entries(&self) -> Entries<'_> {
+        Box::new(self.blobs.iter().map(|blob| {
+            // The body is only read if the blob passes the globs and size limit and
+            // its content has to be sniffed.
+            let size = self.blob_size(&blob.origin.blob)?;
+            let mut read_error = None;
+            let reason = self.inner.skip_reason(&blob.path, size, || {
+                match self.read_blob(&blob.origin.blob) {
+                    Ok(bytes) => Some(sniff(&bytes)),
+                    Err(e) => {
+                        read_error = Some(e);
+                        None
+                    }
+                }
+            });
+            if let Some(e) = read_error {
+                return Err(e.into());
+            }
+            Ok(match reason {
+                Some(reason) => Entry::Skipped(SkippedFile {
+                    id: blob.id.clone(),
+                    reason,
+                }),
+                None => Entry::File(blob.id.clone()),
+            })
+        }))
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        let blob = self.blob(id).ok_or_else(|| not_found(id))?;
+        let bytes = self.read_blob(&blob.origin.blob)?;
+        self.inner.decode(&blob.path, bytes)
+    }
+
+    fn origin(&self, id: &FileId) -> Option<(&Path, &BlobOrigin)> {
+        self.blob(id).map(|b| (b.path.as_path(), &b.origin))
+    }
+}
+
+/// Run `f` on the `git cat-file <mode>` process in `slot`, starting it if needed.
+fn with_cat_file<T>(
+    slot: &Mutex<Option<CatFile>>,
+    repo: &Path,
+    mode: &str,
+    f: impl FnOnce(&mut CatFile) -> io::Result<T>,
+) -> io::Result<T> {
+    let mut cat_file = slot.lock().unwrap_or_else(|e| e.into_inner());
+    if cat_file.is_none() {
+        *cat_file = Some(CatFile::spawn(repo, mode)?);
+    }
+    let result = cat_file
+        .as_mut()
+        .map_or_else(|| Err(io::Error::other("git cat-file is not running")), f);
+    if result.is_err() {
+        // The stream may be out of step; start afresh for the next blob.
+        *cat_file = None;
+    }
+    result
+}
+
+/// A running `git cat-file --batch` or `--batch-check`.
+struct CatFile {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl CatFile {
+    fn spawn(repo: &Path, mode: &str) -> io::Result<Self> {
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["cat-file", mode])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other("git cat-file has no pipes"));
+        };
+        Ok(Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Size of the blob `oid`, from the header both modes print. With `--batch`
+    /// the content follows and must be read next.
+    fn header(&mut self, oid: &str) -> io::Result<usize> {
+        writeln!(self.stdin, "{}", oid)?;
+        self.stdin.flush()?;
+        // <oid> <type> <size>, or <oid> missing
+        let mut header = String::new();
+        self.stdout.read_line(&mut header)?;
+        match header.split_whitespace().collect::<Vec<_>>()[..] {
+            [_, "blob", size] => size
+                .parse::<usize>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("blob {}: {}", oid, header.trim()),
+            )),
+        }
+    }
+
+    /// Content of the blob `oid`; only for `--batch`.
+    fn read(&mut self, oid: &str) -> io::Result<Vec<u8>> {
+        let size = self.header(oid)?;
+        let mut content = vec![0; size + 1];
+        self.stdout.read_exact(&mut content)?;
+        content.pop();
+        Ok(content)
+    }
+}
+
+impl Drop for CatFile {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Each regular-file blob in `git log --raw` output with the path and commit it
+/// first appeared at, in log order.
+fn parse_log(log: &[u8]) -> Vec<(String, BlobOrigin)> {
+    let mut seen = HashSet::new();
+    let mut blobs = Vec::new();
+    let mut commit = ("", "");
+    let log = String::from_utf8_lossy(log);
+    for line in log.lines() {
+        if let Some(header) = line.strip_prefix("commit ") {
+            commit = header.split_once(' ').unwrap_or((header, ""));
+        } else if let Some(raw) = line.strip_prefix(':') {
+            // :<old mode> <new mode> <old oid> <new oid> <status>\t<path>
+            let Some((fields, path)) = raw.split_once('\t') else {
+                continue;
+            };
+            let fields: Vec<&str> = fields.split(' ').collect();
+            let [_, mode, _, oid, status] = fields[..] else {
+                continue;
+            };
+            let regular_file = mode == "100644" || mode == "100755";
+            if status == "D" || !regular_file || !seen.insert(oid.to_string()) {
+                continue;
+            }
+            blobs.push((
+                unquote(path),
+                BlobOrigin {
+                    blob: oid.to_string(),
+                    commit: commit.0.to_string(),
+                    author_date: commit.1.to_string(),
+                },
+            ));
+        }
+    }
+    blobs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_source::SkipReason;
+    use std::fs;
+
+    fn commit(repo: &Path, message: &str, date: &str) {
+        for args in [&["add", "-A"][..], &["commit", "-q", "-m", message]] {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let log = b"commit aaa 2024-01-02T03:04:05+07:00\n\
+            \n\
+            :000000 100644 0000 b1 A\tnotes.txt\n\
+            :000000 120000 0000 b9 A\tlink\n\
+            commit bbb 2024-02-01T00:00:00+07:00\n\
+            \n\
+            :100644 100644 b1 b2 M\tnotes.txt\n\
+            :000000 100644 0000 b1 A\t\"copy \\\"1\\\".txt\"\n\
+            :100644 000000 b2 0000 D\tnotes.txt\n";
+        let blobs = parse_log(log);
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blobs[0].0, "notes.txt");
+        assert_eq!(
+            blobs[0].1,
+            BlobOrigin {
+                blob: "b1".to_string(),
+                commit: "aaa".to_string(),
+                author_date: "2024-01-02T03:04:05+07:00".to_string(),
+            }
+        );
+        assert_eq!(blobs[1].1.blob, "b2");
+        assert_eq!(blobs[1].1.commit, "bbb");
+    }
+
+    #[test]
+    fn test_deleted_files_are_listed_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["init", "-q"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        fs::write(repo.join("export.csv"), "nik\n3171234567890123\n").unwrap();
+        fs::write(repo.join("readme.md"), "hello\n").unwrap();
+        commit(repo, "add export", "2024-01-02T03:04:05+07:00");
+        fs::copy(repo.join("export.csv"), repo.join("copy.csv")).unwrap();
+        fs::remove_file(repo.join("export.csv")).unwrap();
+        commit(repo, "remove export", "2024-03-01T00:00:00+07:00");
+
+        let source = GitHistorySource::new(LocalFileSource::new(repo, vec![])).unwrap();
+        let files = source.listing().unwrap().files;
+        assert_eq!(files.len(), 2);
+        let export = files
+            .iter()
+            .find(|f| f.to_string().ends_with(":export.csv"))
+            .unwrap();
+        let (path, origin) = source.origin(export).unwrap();
+        assert_eq!(path, repo.join("export.csv"));
+        assert_eq!(origin.author_date, "2024-01-02T03:04:05+07:00");
+        assert_eq!(origin.blob.len(), 40);
+        assert!(export.to_string().starts_with(&origin.commit));
+        assert_eq!(source.read_file(export).unwrap(), "nik\n3171234567890123\n");
+        assert!(source.origin(&"readme.md".into()).is_none());
+    }
+
+    #[test]
+    fn test_large_blobs_skipped_without_reading_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["init", "-q"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        fs::write(repo.join("dump.csv"), "3171234567890123\n".repeat(100)).unwrap();
+        commit(repo, "add dump", "2024-01-02T03:04:05+07:00");
+
+        let source =
+            GitHistorySource::new(LocalFileSource::new(repo, vec![]).with_max_file_size(Some(64)))
+                .unwrap();
+        let listing = source.listing().unwrap();
+        assert!(listing.files.is_empty());
+        assert_eq!(listing.skipped.len(), 1);
+        assert_eq!(listing.skipped[0].reason, SkipReason::TooLarge);
+        // Only the size was asked for; no blob content was read.
+        assert!(source.cat_file.lock().unwrap().is_none());
+    }
+}
@@ -0,0 +1,123 @@
+//! Files held in memory, for library users that already have the content and for
+//! tests.
+
+use super::{
+    Entries, Entry, FileId, FileSource, FileSourceError, SkipReason, SkippedFile, not_found,
+};
+use crate::content::{ContentKind, sniff};
+use crate::encoding::DecodedText;
+use std::collections::HashMap;
+
+/// Files held in memory, listed in the order they were added.
+///
+/// Content is sniffed like files on disk: binary files are skipped, and text is
+/// decoded from its byte-order mark, as UTF-8, or in a detected legacy encoding.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFileSource {
+    files: Vec<(FileId, Vec<u8>)>,
+    /// Index into `files` by ID.
+    ids: HashMap<FileId, usize>,
+}
+
+impl MemoryFileSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the file `id`, replacing any file with the same ID.
+    pub fn insert(&mut self, id: impl Into<FileId>, content: impl Into<Vec<u8>>) {
+        let id = id.into();
+        let content = content.into();
+        match self.ids.get(&id) {
+            Some(&i) => self.files[i].1 = content,
+            None => {
+                self.ids.insert(id.clone(), self.files.len());
+                self.files.push((id, content));
+            }
+        }
+    }
+
+    pub fn with_file(mut self, id: impl Into<FileId>, content: impl Into<Vec<u8>>) -> Self {
+        self.insert(id, content);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl<I: Into<FileId>, C: Into<Vec<u8>>> FromIterator<(I, C)> for MemoryFileSource {
+    fn from_iter<T: IntoIterator<Item = (I, C)>>(iter: T) -> Self {
+        let mut source = Self::new();
+        for (id, content) in iter {
+            source.insert(id, content);
+        }
+        source
+    }
+}
+
+impl FileSource for MemoryFileSource {
+    fn entries(&self) -> Entries<'_> {
+        Box::new(self.files.iter().map(|(id, content)| {
+            Ok(match sniff(content) {
+                ContentKind::Text => Entry::File(id.clone()),
+                ContentKind::Binary => Entry::Skipped(SkippedFile {
+                    id: id.clone(),
+                    reason: SkipReason::Binary,
+                }),
+            })
+        }))
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        let &i = self.ids.get(id).ok_or_else(|| not_found(id))?;
+        let content = &self.files[i].1;
+        if sniff(content) == ContentKind::Binary {
+            return Err(FileSourceError::NotTextFile(id.as_path().to_path_buf()));
+        }
+        Ok(DecodedText::decode(content, None)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_source() {
+        let mut source: MemoryFileSource = [
+            (
+                "s3://exports/ward-3.csv",
+                b"Jos\xE9,3171234567890123".to_vec(),
+            ),
+            ("blob-7", b"\x89PNG\r\n\x1a\n\0\0".to_vec()),
+        ]
+        .into_iter()
+        .collect();
+        source.insert("blob-7", "SSN 123-45-6789");
+        assert_eq!(source.len(), 2);
+
+        let files: Vec<FileId> = source.files().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            files,
+            vec![
+                FileId::from("s3://exports/ward-3.csv"),
+                FileId::from("blob-7")
+            ]
+        );
+        let decoded = source.read_decoded(&files[0]).unwrap();
+        assert_eq!(decoded.text, "José,3171234567890123");
+        assert_eq!(decoded.encoding_name(), "windows-1252");
+        assert!(
+            source
+                .read_file(&"missing".into())
+                .unwrap_err()
+                .is_not_found()
+        );
+    }
+}
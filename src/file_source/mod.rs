@@ -0,0 +1,934 @@
+mod adapters;
+mod archive;
+mod git;
+mod history;
+mod memory;
+
+pub use adapters::{Chain, Filter, Map};
+
+pub use archive::{
+    ARCHIVE_SEPARATOR, ArchiveFileSource, ArchiveKind, ArchiveLimits, DEFAULT_ARCHIVE_MAX_SIZE,
+};
+pub use git::{ChangedFile, GitDiffSource, GitScope};
+pub use history::{BlobOrigin, GitHistorySource};
+pub use memory::MemoryFileSource;
+
+use crate::content::{ContentKind, Extractor, FileTypes, SNIFF_LEN, sniff};
+use crate::encoding::{DecodedText, EncodingRules};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+#[derive(Debug)]
+pub enum FileSourceError {
+    Io(io::Error),
+    NotTextFile(PathBuf),
+}
+
+impl std::fmt::Display for FileSourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileSourceError::Io(e) => write!(f, "IO error: {}", e),
+            FileSourceError::NotTextFile(p) => write!(f, "Not a text file: {}", p.display()),
+        }
+    }
+}
+
+impl std::error::Error for FileSourceError {}
+
+impl FileSourceError {
+    /// Whether the file does not exist in the source.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, FileSourceError::Io(e) if e.kind() == io::ErrorKind::NotFound)
+    }
+}
+
+impl From<io::Error> for FileSourceError {
+    fn from(e: io::Error) -> Self {
+        FileSourceError::Io(e)
+    }
+}
+
+/// Why a file was not scanned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Matched an `--exclude` glob (a file, or a directory that was not entered).
+    Excluded,
+    /// `--include` globs were given and none matched.
+    NotIncluded,
+    /// Larger than `--max-file-size`.
+    TooLarge,
+    /// File type not scanned: not in the extension list, or mapped to the `skip`
+    /// extractor.
+    Extension,
+    /// Sniffed as binary.
+    Binary,
+    /// Symlinks are not followed.
+    Symlink,
+    /// A followed symlink leads back to one of its own parent directories.
+    SymlinkLoop,
+    /// A followed symlink points outside the scanned directory.
+    SymlinkOutsideRoot,
+    /// A followed symlink points to nothing.
+    BrokenSymlink,
+    /// The walker could not read the entry, or an archive could not be read.
+    Unreadable,
+    /// An archive nested deeper than the archive depth limit.
+    ArchiveDepth,
+    /// The archive's decompressed size limit was reached; later entries were not read.
+    ArchiveLimit,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            SkipReason::Excluded => "matched an exclude glob",
+            SkipReason::NotIncluded => "matched no include glob",
+            SkipReason::TooLarge => "larger than the maximum file size",
+            SkipReason::Extension => "file type not scanned",
+            SkipReason::Binary => "binary",
+            SkipReason::Symlink => "symlink not followed",
+            SkipReason::SymlinkLoop => "symlink loop",
+            SkipReason::SymlinkOutsideRoot => "symlink points outside the scanned directory",
+            SkipReason::BrokenSymlink => "broken symlink",
+            SkipReason::Unreadable => "unreadable",
+            SkipReason::ArchiveDepth => "archive nested too deeply",
+            SkipReason::ArchiveLimit => "archive size limit reached",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// Identifies a file within its [`FileSource`]: a path on disk, a virtual path such
+/// as `export.zip!/patients/2024.csv`, or any name an in-memory source was given.
+///
+/// Globs, path rules and allowlists match it as a path.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(PathBuf);
+
+impl FileId {
+    pub fn new(id: impl Into<PathBuf>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for FileId {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl From<PathBuf> for FileId {
+    fn from(path: PathBuf) -> Self {
+        Self(path)
+    }
+}
+
+impl From<&Path> for FileId {
+    fn from(path: &Path) -> Self {
+        Self(path.to_path_buf())
+    }
+}
+
+impl From<String> for FileId {
+    fn from(id: String) -> Self {
+        Self(id.into())
+    }
+}
+
+impl From<&str> for FileId {
+    fn from(id: &str) -> Self {
+        Self(id.into())
+    }
+}
+
+impl std::fmt::Display for FileId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
+
+/// A file the source passed over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    pub id: FileId,
+    pub reason: SkipReason,
+}
+
+/// One item of a source's [`entries`](FileSource::entries).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    /// A file to scan.
+    File(FileId),
+    /// A file passed over.
+    Skipped(SkippedFile),
+}
+
+/// Entries of a source, produced as they are found.
+pub type Entries<'a> = Box<dyn Iterator<Item = Result<Entry, FileSourceError>> + 'a>;
+
+/// The files a source will yield, and those it skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Listing {
+    pub files: Vec<FileId>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+/// Files to scan, from disk, archives, git or memory.
+///
+/// Sources produce their files lazily through [`entries`](Self::entries) and are
+/// combined like iterators with [`chain`](Self::chain), [`filter`](Self::filter)
+/// and [`map`](Self::map).
+pub trait FileSource {
+    /// The files to scan and the files passed over, as they are found. An error
+    /// does not necessarily end the iteration.
+    fn entries(&self) -> Entries<'_>;
+
+    /// Text to scan from the file `id`, keeping its original encoding so offsets and
+    /// redacted text can be mapped back. An unknown `id` is an `Io` error of kind
+    /// `NotFound` (see [`FileSourceError::is_not_found`]).
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError>;
+
+    fn read_file(&self, id: &FileId) -> Result<String, FileSourceError> {
+        self.read_decoded(id).map(|decoded| decoded.text)
+    }
+
+    /// The files to scan, as they are found.
+    fn files(&self) -> Box<dyn Iterator<Item = Result<FileId, FileSourceError>> + '_> {
+        Box::new(self.entries().filter_map(|entry| match entry {
+            Ok(Entry::File(id)) => Some(Ok(id)),
+            Ok(Entry::Skipped(_)) => None,
+            Err(e) => Some(Err(e)),
+        }))
+    }
+
+    /// All entries, collected; fails on the first error.
+    fn listing(&self) -> Result<Listing, FileSourceError> {
+        let mut listing = Listing::default();
+        for entry in self.entries() {
+            match entry? {
+                Entry::File(id) => listing.files.push(id),
+                Entry::Skipped(skipped) => listing.skipped.push(skipped),
+            }
+        }
+        Ok(listing)
+    }
+
+    /// Lines of `id` (1-based, half-open ranges) whose findings are reported, or
+    /// `None` for the whole file. Sources over a change report only what it added.
+    fn reported_lines(&self, _id: &FileId) -> Option<&[Range<u32>]> {
+        None
+    }
+
+    /// For a file version read from history, its path in the tree and where it was
+    /// committed. `None` for files read as they are now.
+    fn origin(&self, _id: &FileId) -> Option<(&Path, &BlobOrigin)> {
+        None
+    }
+
+    /// The entries of `self`, then those of `other`. Reads go to `self`, then to
+    /// `other` if `self` does not have the file.
+    fn chain<S: FileSource>(self, other: S) -> Chain<Self, S>
+    where
+        Self: Sized,
+    {
+        Chain::new(self, other)
+    }
+
+    /// Only the files whose ID satisfies `predicate`; the others are not listed.
+    fn filter<P: Fn(&FileId) -> bool>(self, predicate: P) -> Filter<Self, P>
+    where
+        Self: Sized,
+    {
+        Filter::new(self, predicate)
+    }
+
+    /// Files transformed by `f` after they are read, e.g. to strip markup before
+    /// scanning. Offsets of findings refer to the text `f` returns.
+    fn map<F: Fn(&FileId, DecodedText) -> DecodedText>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+    {
+        Map::new(self, f)
+    }
+}
+
+impl<S: FileSource + ?Sized> FileSource for Box<S> {
+    fn entries(&self) -> Entries<'_> {
+        (**self).entries()
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        (**self).read_decoded(id)
+    }
+
+    fn read_file(&self, id: &FileId) -> Result<String, FileSourceError> {
+        (**self).read_file(id)
+    }
+
+    fn reported_lines(&self, id: &FileId) -> Option<&[Range<u32>]> {
+        (**self).reported_lines(id)
+    }
+
+    fn origin(&self, id: &FileId) -> Option<(&Path, &BlobOrigin)> {
+        (**self).origin(id)
+    }
+}
+
+/// The `NotFound` error for a file `id` the source does not have.
+pub(crate) fn not_found(id: &FileId) -> FileSourceError {
+    FileSourceError::Io(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{}: no such file in this source", id),
+    ))
+}
+
+/// Default for [`LocalFileSource::with_max_file_size`] (ARCHITECTURE §8.5).
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Per-directory ignore file read in addition to `.gitignore`.
+pub const IGNORE_FILE: &str = ".healthwandignore";
+
+/// Files under a local path.
+///
+/// Directories are walked with the `ignore` crate, so `.gitignore`, `.ignore` and
+/// [`IGNORE_FILE`] rules apply (inside or outside a git repository), and hidden
+/// entries are skipped unless enabled. Entries pruned that way are not reported;
+/// files rejected by the globs, size limit or file type are.
+///
+/// Symlinks are skipped unless followed with [`Self::with_follow_symlinks`].
+pub struct LocalFileSource {
+    pub root: PathBuf,
+    pub file_types: FileTypes,
+    include: GlobSet,
+    exclude: GlobSet,
+    glob_root: PathBuf,
+    hidden: bool,
+    follow_symlinks: bool,
+    symlinks_outside_root: bool,
+    max_file_size: Option<u64>,
+    encodings: EncodingRules,
+}
+
+impl LocalFileSource {
+    /// Files under `root`. With no `allowed_extensions`, files are sniffed by content
+    /// (see [`crate::content`]); otherwise only those extensions are read.
+    pub fn new<P: Into<PathBuf>>(root: P, allowed_extensions: Vec<String>) -> Self {
+        Self {
+            root: root.into(),
+            file_types: FileTypes::new(allowed_extensions),
+            include: GlobSet::empty(),
+            exclude: GlobSet::empty(),
+            glob_root: PathBuf::from("."),
+            hidden: false,
+            follow_symlinks: false,
+            symlinks_outside_root: false,
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+            encodings: EncodingRules::default(),
+        }
+    }
+
+    /// Only yield files matching one of `globs` (all files if empty).
+    pub fn with_include(mut self, globs: &[String]) -> Result<Self, globset::Error> {
+        self.include = glob_set(globs)?;
+        Ok(self)
+    }
+
+    /// Skip files matching any of `globs`; directories matching one are not entered.
+    pub fn with_exclude(mut self, globs: &[String]) -> Result<Self, globset::Error> {
+        self.exclude = glob_set(globs)?;
+        Ok(self)
+    }
+
+    /// Directory the include, exclude and encoding globs are relative to (the
+    /// repository root); defaults to the working directory.
+    pub fn with_glob_root<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.glob_root = absolute(dir.as_ref());
+        self
+    }
+
+    /// `path` as the globs see it: relative to the glob root.
+    pub(crate) fn glob_path(&self, path: &Path) -> PathBuf {
+        relative_to(&self.glob_root, path)
+    }
+
+    /// Also walk hidden files and directories.
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Follow symlinks to files and directories under the root.
+    ///
+    /// A directory symlink leading back to one of its parents (compared by device
+    /// and inode) is reported as [`SkipReason::SymlinkLoop`] instead of being
+    /// entered again, and one whose target is outside the root as
+    /// [`SkipReason::SymlinkOutsideRoot`] unless [`Self::with_symlinks_outside_root`]
+    /// allows it.
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// When following symlinks, also follow those pointing outside the root.
+    pub fn with_symlinks_outside_root(mut self, allow: bool) -> Self {
+        self.symlinks_outside_root = allow;
+        self
+    }
+
+    /// Skip files larger than `bytes`; `None` disables the limit.
+    pub fn with_max_file_size(mut self, bytes: Option<u64>) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Extractor per extension, overriding the extension list and sniffing.
+    pub fn with_extractors(mut self, extractors: BTreeMap<String, Extractor>) -> Self {
+        self.file_types.extractors = extractors;
+        self
+    }
+
+    /// Decode files matching `encodings` in the configured encoding instead of
+    /// detecting it.
+    pub fn with_encodings(mut self, encodings: EncodingRules) -> Self {
+        self.encodings = encodings;
+        self
+    }
+
+    /// Text to scan from the content of `path`, using its extractor and encoding.
+    fn decode(&self, path: &Path, bytes: Vec<u8>) -> Result<DecodedText, FileSourceError> {
+        self.file_types
+            .extractor(path)
+            .extract(bytes, self.encodings.for_path(&self.glob_path(path)))?
+            .ok_or_else(|| FileSourceError::NotTextFile(path.to_path_buf()))
+    }
+
+    /// Why `path` (a regular file of `len` bytes) is skipped, if it is. `sniff`
+    /// classifies its content, or returns `None` if it cannot be read.
+    fn skip_reason(
+        &self,
+        path: &Path,
+        len: u64,
+        sniff: impl FnOnce() -> Option<ContentKind>,
+    ) -> Option<SkipReason> {
+        let relative = self.glob_path(path);
+        if self.exclude.is_match(&relative) {
+            Some(SkipReason::Excluded)
+        } else if !self.include.is_empty() && !self.include.is_match(&relative) {
+            Some(SkipReason::NotIncluded)
+        } else if self.max_file_size.is_some_and(|max| len > max) {
+            Some(SkipReason::TooLarge)
+        } else {
+            match self.file_types.extractor(path) {
+                Extractor::Skip => Some(SkipReason::Extension),
+                Extractor::Auto
+                    if self.encodings.for_path(path).is_none()
+                        && sniff() == Some(ContentKind::Binary) =>
+                {
+                    Some(SkipReason::Binary)
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+impl LocalFileSource {
+    /// The entry for a walked file or walk error.
+    fn walk_entry(&self, entry: Result<ignore::DirEntry, ignore::Error>) -> Option<Entry> {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let (path, reason) = match walk_error_loop(&e) {
+                    Some(child) => (child.to_path_buf(), SkipReason::SymlinkLoop),
+                    None => {
+                        let path = walk_error_path(&e).unwrap_or_else(|| self.root.clone());
+                        let broken = self.follow_symlinks
+                            && fs::symlink_metadata(&path).is_ok_and(|m| m.is_symlink());
+                        let reason = if broken {
+                            SkipReason::BrokenSymlink
+                        } else {
+                            SkipReason::Unreadable
+                        };
+                        (path, reason)
+                    }
+                };
+                warn!("Skipping {}: {}", path.display(), e);
+                return Some(Entry::Skipped(SkippedFile {
+                    id: path.into(),
+                    reason,
+                }));
+            }
+        };
+        let file_type = entry.file_type()?;
+        let path = entry.into_path();
+        if file_type.is_symlink() {
+            return Some(Entry::Skipped(SkippedFile {
+                id: path.into(),
+                reason: SkipReason::Symlink,
+            }));
+        }
+        if !file_type.is_file() {
+            return None;
+        }
+        let len = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Some(match self.skip_reason(&path, len, || sniff_file(&path)) {
+            Some(reason) => {
+                if reason == SkipReason::TooLarge {
+                    warn!(
+                        "Skipping {}: {} bytes exceeds the maximum file size",
+                        path.display(),
+                        len
+                    );
+                }
+                Entry::Skipped(SkippedFile {
+                    id: path.into(),
+                    reason,
+                })
+            }
+            None => Entry::File(path.into()),
+        })
+    }
+}
+
+impl FileSource for LocalFileSource {
+    fn entries(&self) -> Entries<'_> {
+        // Fail on a missing or unreadable root rather than yielding nothing.
+        if let Err(e) = fs::metadata(&self.root) {
+            return Box::new(std::iter::once(Err(e.into())));
+        }
+        // Only followed symlinks need checking; without them the walk cannot leave
+        // the root.
+        let mut root = None;
+        if self.follow_symlinks && !self.symlinks_outside_root {
+            match fs::canonicalize(&self.root) {
+                Ok(canonical) => root = Some(canonical),
+                Err(e) => return Box::new(std::iter::once(Err(e.into()))),
+            }
+        }
+        // Excluded directories and symlinks out of the root are not entered; each is
+        // reported once, as soon as the walker has pruned it.
+        let exclude = self.exclude.clone();
+        let glob_root = self.glob_root.clone();
+        let pruned = Arc::new(Mutex::new(Vec::new()));
+        let pruned_entries = Arc::clone(&pruned);
+        let walker = WalkBuilder::new(&self.root)
+            .hidden(!self.hidden)
+            .require_git(false)
+            .follow_links(self.follow_symlinks)
+            .add_custom_ignore_filename(IGNORE_FILE)
+            .filter_entry(move |entry| {
+                let outside_root = entry.depth() > 0
+                    && entry.path_is_symlink()
+                    && root.as_ref().is_some_and(|root| {
+                        fs::canonicalize(entry.path()).is_ok_and(|target| !target.starts_with(root))
+                    });
+                let excluded = entry.depth() > 0
+                    && entry.file_type().is_some_and(|t| t.is_dir())
+                    && exclude.is_match(relative_to(&glob_root, entry.path()));
+                let reason = if outside_root {
+                    warn!(
+                        "Skipping {}: {}",
+                        entry.path().display(),
+                        SkipReason::SymlinkOutsideRoot
+                    );
+                    Some(SkipReason::SymlinkOutsideRoot)
+                } else if excluded {
+                    Some(SkipReason::Excluded)
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    if let Ok(mut entries) = pruned_entries.lock() {
+                        entries.push((entry.path().to_path_buf(), reason));
+                    }
+                }
+                reason.is_none()
+            })
+            .build();
+        let drain_pruned = move || {
+            let entries = std::mem::take(&mut *pruned.lock().unwrap_or_else(|e| e.into_inner()));
+            entries.into_iter().map(|(path, reason)| {
+                Ok(Entry::Skipped(SkippedFile {
+                    id: path.into(),
+                    reason,
+                }))
+            })
+        };
+        let walked = walker
+            .map(Some)
+            .chain(std::iter::once(None))
+            .flat_map(move |entry| {
+                let walked = entry.and_then(|entry| self.walk_entry(entry)).map(Ok);
+                drain_pruned().chain(walked)
+            });
+        Box::new(walked)
+    }
+
+    fn read_decoded(&self, id: &FileId) -> Result<DecodedText, FileSourceError> {
+        let path = id.as_path();
+        if self.file_types.extractor(path) == Extractor::Skip {
+            return Err(FileSourceError::NotTextFile(path.to_path_buf()));
+        }
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        self.decode(path, bytes)
+    }
+}
+
+/// `path` without leading `./` components, so `./docs/a.md` matches `docs/**`.
+pub(crate) fn strip_cur_dir(path: &Path) -> PathBuf {
+    path.components()
+        .skip_while(|c| matches!(c, Component::CurDir))
+        .collect()
+}
+
+/// `path` relative to `dir`, for matching globs written relative to a directory
+/// other than the one healthwand runs from. Paths outside `dir` are only stripped
+/// of leading `./`.
+pub(crate) fn relative_to(dir: &Path, path: &Path) -> PathBuf {
+    let path = strip_cur_dir(path);
+    if let Ok(relative) = path.strip_prefix(strip_cur_dir(dir)) {
+        return relative.to_path_buf();
+    }
+    match absolute(&path).strip_prefix(absolute(dir)) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path,
+    }
+}
+
+/// `path` made absolute against the working directory, without touching the
+/// filesystem; unchanged if the working directory is unknown.
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Sniff the start of the file at `path`; `None` if it cannot be read, leaving the
+/// error to [`FileSource::read_file`].
+fn sniff_file(path: &Path) -> Option<ContentKind> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)
+        .and_then(|f| f.take(SNIFF_LEN as u64).read_to_end(&mut head))
+        .ok()?;
+    Some(sniff(&head))
+}
+
+fn glob_set(globs: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob)?);
+    }
+    builder.build()
+}
+
+/// The symlink that closes the loop, if `error` is a symlink loop.
+fn walk_error_loop(error: &ignore::Error) -> Option<&Path> {
+    match error {
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => walk_error_loop(err),
+        _ => None,
+    }
+}
+
+fn walk_error_path(error: &ignore::Error) -> Option<PathBuf> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path.clone()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            walk_error_path(err)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_file_traversal_and_reading() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        let mut file = File::create(&file_path).unwrap();
+        writeln!(file, "Hello, world!").unwrap();
+
+        let fs = LocalFileSource::new(dir.path(), vec!["txt".to_string()]);
+        let files = fs.listing().unwrap().files;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].as_path().extension().unwrap(), "txt");
+
+        let content = fs.read_file(&files[0]).unwrap();
+        assert!(content.contains("Hello, world!"));
+    }
+
+    #[test]
+    fn test_non_text_file_filtered() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("image.png");
+        let mut file = File::create(&file_path).unwrap();
+        writeln!(file, "not really an image").unwrap();
+
+        let fs = LocalFileSource::new(dir.path(), vec!["txt".to_string()]);
+        let files = fs.listing().unwrap().files;
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_files_nonexistent_root() {
+        let fs = LocalFileSource::new("/this/path/should/not/exist", vec!["txt".to_string()]);
+        let result = fs.listing();
+        assert!(result.is_err());
+        match result {
+            Err(FileSourceError::Io(_)) => {}
+            _ => panic!("Expected Io error for nonexistent root"),
+        }
+    }
+
+    #[test]
+    fn test_files_on_regular_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("single.txt");
+        let mut file = File::create(&file_path).unwrap();
+        writeln!(file, "test").unwrap();
+        let fs = LocalFileSource::new(&file_path, vec!["txt".to_string()]);
+        let files = fs.listing().unwrap().files;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].as_path(), file_path);
+    }
+
+    #[test]
+    fn test_read_file_not_allowed_extension() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("notallowed.bin");
+        let mut file = File::create(&file_path).unwrap();
+        writeln!(file, "data").unwrap();
+        let fs = LocalFileSource::new(dir.path(), vec!["txt".to_string()]);
+        let result = fs.read_file(&file_path.clone().into());
+        assert!(result.is_err());
+        match result {
+            Err(FileSourceError::NotTextFile(p)) => assert_eq!(p, file_path),
+            _ => panic!("Expected NotTextFile error for disallowed extension"),
+        }
+    }
+
+    fn write(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn names(root: &Path, files: &[FileId]) -> Vec<String> {
+        let mut names: Vec<String> = files
+            .iter()
+            .map(|f| {
+                f.as_path()
+                    .strip_prefix(root)
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_ignore_files_prune_walk() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".gitignore", "target/\nnode_modules/\n*.log.txt\n");
+        write(root, ".healthwandignore", "fixtures/\n");
+        write(root, "notes.txt", "a");
+        write(root, "debug.log.txt", "a");
+        write(root, "target/out.txt", "a");
+        write(root, "web/node_modules/pkg/readme.md", "a");
+        write(root, "tests/fixtures/patient.csv", "a");
+        write(root, ".secrets/keys.txt", "a");
+
+        let exts = vec!["txt".to_string(), "md".to_string(), "csv".to_string()];
+        let listing = LocalFileSource::new(root, exts.clone()).listing().unwrap();
+        assert_eq!(names(root, &listing.files), vec!["notes.txt"]);
+        assert!(
+            listing.skipped.is_empty(),
+            "ignored entries are not reported"
+        );
+
+        let hidden = LocalFileSource::new(root, exts).with_hidden(true);
+        assert_eq!(
+            names(root, &hidden.listing().unwrap().files),
+            vec![".secrets/keys.txt", "notes.txt"]
+        );
+    }
+
+    #[test]
+    fn test_globs_and_size_limit_report_skips() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, "src/a.txt", "a");
+        write(root, "src/big.txt", &"x".repeat(2048));
+        write(root, "src/image.png", "a");
+        write(root, "docs/b.txt", "a");
+        write(root, "vendor/c.txt", "a");
+
+        let source = LocalFileSource::new(root, vec!["txt".to_string()])
+            .with_include(&["**/src/**".to_string(), "**/vendor/**".to_string()])
+            .unwrap()
+            .with_exclude(&["**/vendor".to_string()])
+            .unwrap()
+            .with_max_file_size(Some(1024));
+        let listing = source.listing().unwrap();
+        assert_eq!(names(root, &listing.files), vec!["src/a.txt"]);
+        let mut skipped: Vec<(String, SkipReason)> = listing
+            .skipped
+            .iter()
+            .map(|s| {
+                (
+                    s.id.as_path()
+                        .strip_prefix(root)
+                        .unwrap()
+                        .display()
+                        .to_string(),
+                    s.reason,
+                )
+            })
+            .collect();
+        skipped.sort();
+        assert_eq!(
+            skipped,
+            vec![
+                ("docs/b.txt".to_string(), SkipReason::NotIncluded),
+                ("src/big.txt".to_string(), SkipReason::TooLarge),
+                ("src/image.png".to_string(), SkipReason::Extension),
+                ("vendor".to_string(), SkipReason::Excluded),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks() {
+        use std::os::unix::fs::symlink;
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        write(&root, "data/a.txt", "a");
+        write(dir.path(), "outside/b.txt", "b");
+        symlink(dir.path().join("outside"), root.join("mounted")).unwrap();
+        symlink(root.join("data"), root.join("data/loop")).unwrap();
+        symlink("data/a.txt", root.join("alias.txt")).unwrap();
+        symlink("missing.txt", root.join("dangling.txt")).unwrap();
+        let listing = |source: LocalFileSource| {
+            let listing = source.listing().unwrap();
+            let mut skipped: Vec<(String, SkipReason)> = listing
+                .skipped
+                .iter()
+                .map(|s| {
+                    let path = s.id.as_path().strip_prefix(&root).unwrap();
+                    (path.display().to_string(), s.reason)
+                })
+                .collect();
+            skipped.sort();
+            (names(&root, &listing.files), skipped)
+        };
+
+        let (files, skipped) = listing(LocalFileSource::new(&root, vec![]));
+        assert_eq!(files, vec!["data/a.txt"]);
+        assert!(skipped.iter().all(|(_, r)| *r == SkipReason::Symlink));
+        assert_eq!(skipped.len(), 4);
+
+        let follow = || LocalFileSource::new(&root, vec![]).with_follow_symlinks(true);
+        let (files, skipped) = listing(follow());
+        assert_eq!(files, vec!["alias.txt", "data/a.txt"]);
+        assert_eq!(
+            skipped,
+            vec![
+                ("dangling.txt".to_string(), SkipReason::BrokenSymlink),
+                ("data/loop".to_string(), SkipReason::SymlinkLoop),
+                ("mounted".to_string(), SkipReason::SymlinkOutsideRoot),
+            ]
+        );
+
+        let (files, _) = listing(follow().with_symlinks_outside_root(true));
+        assert_eq!(files, vec!["alias.txt", "data/a.txt", "mounted/b.txt"]);
+    }
+
+    #[test]
+    fn test_content_sniffing_without_extension_list() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "messages/adt.hl7",
+            "MSH|^~\\&|SIMRS\rPID|||3171234567890123\r",
+        );
+        write(root, "Dockerfile", "FROM scratch\n");
+        fs::write(root.join("scan.dcm"), b"DICM\0\0\x01\x02").unwrap();
+
+        let source = LocalFileSource::new(root, Vec::new());
+        let listing = source.listing().unwrap();
+        assert_eq!(
+            names(root, &listing.files),
+            vec!["Dockerfile", "messages/adt.hl7"]
+        );
+        assert_eq!(listing.skipped.len(), 1);
+        assert_eq!(listing.skipped[0].reason, SkipReason::Binary);
+        assert!(matches!(
+            source.read_file(&root.join("scan.dcm").into()),
+            Err(FileSourceError::NotTextFile(_))
+        ));
+
+        let strings =
+            source.with_extractors(BTreeMap::from([("dcm".to_string(), Extractor::Strings)]));
+        assert_eq!(strings.files().count(), 3);
+        assert_eq!(
+            strings.read_file(&root.join("scan.dcm").into()).unwrap(),
+            "DICM\n\n\n\n"
+        );
+    }
+
+    #[test]
+    fn test_read_file_permission_denied() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("private.txt");
+        let mut file = File::create(&file_path).unwrap();
+        writeln!(file, "secret").unwrap();
+        drop(file);
+        // Remove read permissions (Unix only)
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&file_path).unwrap().permissions();
+            perms.set_mode(0o000);
+            fs::set_permissions(&file_path, perms).unwrap();
+            let fs = LocalFileSource::new(dir.path(), vec!["txt".to_string()]);
+            let result = fs.read_file(&file_path.clone().into());
+            assert!(result.is_err());
+            match result {
+                Err(FileSourceError::Io(e)) => {
+                    assert!(e.kind() == std::io::ErrorKind::PermissionDenied);
+                }
+                _ => panic!("Expected Io error for permission denied"),
+            }
+            // Restore permissions so tempdir can clean up
+            let mut perms = fs::metadata(&file_path).unwrap().permissions();
+            perms.set_mode(0o644);
+            fs::set_permissions(&file_path, perms).unwrap();
+        }
+    }
+}
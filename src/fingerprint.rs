@@ -0,0 +1,114 @@
+//! Stable identities for findings.
+//!
+//! Byte offsets change whenever a line is added above a match, so a fingerprint is
+//! derived from the pattern ID, a keyed hash of the normalized match and a hash of
+//! the words around it. Whitespace, punctuation and case are ignored, which keeps the
+//! fingerprint stable across line shifts and reformatting.
+//!
+//! The match is hashed with HMAC-SHA256 because identifiers such as NIKs have a small
+//! enough space to brute-force a plain hash. Organisations should set their own key
+//! (the `fingerprint_key` setting in the CLI); changing it changes every fingerprint.
+
+use crate::domain::Finding;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// Key used when none is configured. Fingerprints are then stable but not secret.
+pub const DEFAULT_KEY: &[u8] = b"healthwand-fingerprint-v1";
+
+/// Number of words on each side of the match that contribute to the context hash.
+const CONTEXT_WORDS: usize = 2;
+
+/// Compute the fingerprint of `finding` within the scanned `text`.
+pub fn fingerprint(key: &[u8], text: &str, finding: &Finding) -> String {
+    let match_hash = keyed_hash(key, normalize(&finding.matched_text).as_bytes());
+    let before = text.get(..finding.span.start).unwrap_or_default();
+    let after = text.get(finding.span.end..).unwrap_or_default();
+    let mut context: Vec<String> = words(before).rev().take(CONTEXT_WORDS).collect();
+    context.reverse();
+    context.push("\u{0}".to_string());
+    context.extend(words(after).take(CONTEXT_WORDS));
+    let context_hash = Sha256::digest(context.join(" ").as_bytes());
+
+    let digest = Sha256::new()
+        .chain_update(finding.pattern_id.as_str().as_bytes())
+        .chain_update([0])
+        .chain_update(match_hash)
+        .chain_update(context_hash)
+        .finalize();
+    hex(&digest[..16])
+}
+
+fn keyed_hash(key: &[u8], data: &[u8]) -> Vec<u8> {
+    Hmac::<Sha256>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length")
+        .chain_update(data)
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
+
+/// Lowercased alphanumerics only: `123-45-6789` and `123 45 6789` normalize alike.
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn words(s: &str) -> impl DoubleEndedIterator<Item = String> + '_ {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fp(id: &str, text: &str, needle: &str) -> String {
+        fingerprint(DEFAULT_KEY, text, &Finding::for_test(id, text, needle))
+    }
+
+    #[test]
+    fn test_fingerprint_survives_line_shifts_and_reformatting() {
+        let original = fp("ssn", "Patient SSN: 123-45-6789 on file", "123-45-6789");
+        let shifted = fp(
+            "ssn",
+            "Header\n\nPatient   SSN:\n  123-45-6789  on file",
+            "123-45-6789",
+        );
+        assert_eq!(original, shifted);
+        assert_eq!(original.len(), 32);
+    }
+
+    #[test]
+    fn test_fingerprint_distinguishes_pattern_value_and_context() {
+        let base = fp("ssn", "Patient SSN: 123-45-6789 on file", "123-45-6789");
+        assert_ne!(
+            base,
+            fp("ssn", "Patient SSN: 987-65-4321 on file", "987-65-4321")
+        );
+        assert_ne!(
+            base,
+            fp("ssn", "Donor SSN: 123-45-6789 on file", "123-45-6789")
+        );
+        assert_ne!(
+            base,
+            fp("mrn", "Patient SSN: 123-45-6789 on file", "123-45-6789")
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_depends_on_key() {
+        let text = "NIK 3171234567890123";
+        let f = Finding::for_test("indonesian_nik", text, "3171234567890123");
+        let org = fingerprint(b"org-key", text, &f);
+        assert_eq!(org, fingerprint(b"org-key", text, &f));
+        assert_ne!(org, fingerprint(DEFAULT_KEY, text, &f));
+    }
+}
@@ -0,0 +1,50 @@
+//! Output formatters.
+//!
+//! A formatter only sees the finished [`OutputBundle`], never the catalogue or the scanner.
+
+use crate::results::OutputBundle;
+use std::io::Write;
+
+pub mod sarif;
+
+pub use sarif::SarifFormatter;
+
+/// Report format selected with `--output` or the `output` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Json,
+    Text,
+    Sarif,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Text => "text",
+            OutputFormat::Sarif => "sarif",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "text" => Ok(OutputFormat::Text),
+            "sarif" => Ok(OutputFormat::Sarif),
+            _ => Err(format!(
+                "unknown output format: {} (expected json, text or sarif)",
+                s
+            )),
+        }
+    }
+}
+
+pub trait Formatter {
+    fn format(&self, bundle: &OutputBundle, writer: &mut dyn Write) -> crate::error::Result<()>;
+}
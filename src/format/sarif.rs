@@ -0,0 +1,170 @@
+//! SARIF 2.1.0 output for code-scanning dashboards.
+//!
+//! Matched text is never written to SARIF: results only name the pattern, and
+//! `partialFingerprints` carries the finding's fingerprint so dashboards can track it
+//! across commits.
+
+use crate::domain::Severity;
+use crate::error::HealthwandError;
+use crate::format::Formatter;
+use crate::results::{DetectionResult, OutputBundle};
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::io::Write;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+/// Key under `partialFingerprints`; bump the suffix if the fingerprint scheme changes.
+pub const FINGERPRINT_KEY: &str = "healthwand/v1";
+
+#[derive(Debug, Default)]
+pub struct SarifFormatter;
+
+impl SarifFormatter {
+    /// Build the SARIF log as a JSON value.
+    pub fn to_value(&self, bundle: &OutputBundle) -> Value {
+        let rule_ids: BTreeSet<&str> = bundle
+            .results
+            .iter()
+            .map(|r| r.pattern_id.as_str())
+            .collect();
+        let rules: Vec<Value> = rule_ids
+            .iter()
+            .map(|id| json!({ "id": id, "shortDescription": { "text": format!("PHI pattern {}", id) } }))
+            .collect();
+        let results: Vec<Value> = bundle.results.iter().map(result).collect();
+        json!({
+            "$schema": SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "healthwand",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }]
+        })
+    }
+}
+
+impl Formatter for SarifFormatter {
+    fn format(&self, bundle: &OutputBundle, writer: &mut dyn Write) -> crate::error::Result<()> {
+        serde_json::to_writer_pretty(&mut *writer, &self.to_value(bundle))
+            .map_err(|e| HealthwandError::IoError(e.into()))?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Informational => "note",
+    }
+}
+
+fn result(r: &DetectionResult) -> Value {
+    let mut value = json!({
+        "ruleId": r.pattern_id.as_str(),
+        "level": level(r.severity),
+        "message": { "text": format!("Possible PHI ({}, severity {})", r.pattern_id, r.severity) },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": r.file_path.replace('\\', "/") },
+                "region": {
+                    "byteOffset": r.location.0,
+                    "byteLength": r.location.1 - r.location.0,
+                }
+            }
+        }],
+    });
+    if let Some(line) = r.line {
+        value["locations"][0]["physicalLocation"]["region"]["startLine"] = json!(line);
+    }
+    if let Some(fp) = &r.fingerprint {
+        value["partialFingerprints"] = json!({ FINGERPRINT_KEY: fp });
+    }
+    if let Some(s) = r.suppression.as_ref().filter(|s| !s.rejected) {
+        let mut suppression = json!({ "kind": "inSource" });
+        if let Some(justification) = &s.justification {
+            suppression["justification"] = json!(justification);
+        }
+        value["suppressions"] = json!([suppression]);
+    }
+    if !r.rules.is_empty() {
+        value["properties"] = json!({ "healthwandRules": r.rules });
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{PatternId, Suppression};
+    use crate::results::ResultsSummary;
+
+    fn detection(suppression: Option<Suppression>) -> DetectionResult {
+        DetectionResult {
+            file_path: "data/intake.txt".to_string(),
+            phi_type: "SSN".to_string(),
+            pattern_id: PatternId::new("ssn".to_string()).unwrap(),
+            severity: Severity::High,
+            location: (13, 24),
+            line: Some(2),
+            context: "SSN: 123-45-6789".to_string(),
+            matched_text: "123-45-6789".to_string(),
+            redacted_text: None,
+            suppression,
+            fingerprint: Some("0123456789abcdef0123456789abcdef".to_string()),
+            rules: Vec::new(),
+            origin: None,
+        }
+    }
+
+    #[test]
+    fn test_sarif_has_fingerprints_and_no_matched_text() {
+        let bundle = OutputBundle {
+            results: vec![detection(None)],
+            summary: ResultsSummary::default(),
+        };
+        let mut out = Vec::new();
+        SarifFormatter.format(&bundle, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(!text.contains("123-45-6789"));
+
+        let sarif: Value = serde_json::from_str(&text).unwrap();
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(result["ruleId"], "ssn");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            2
+        );
+        assert_eq!(
+            result["partialFingerprints"][FINGERPRINT_KEY],
+            "0123456789abcdef0123456789abcdef"
+        );
+        assert!(result.get("suppressions").is_none());
+    }
+
+    #[test]
+    fn test_sarif_marks_suppressed_results() {
+        let bundle = OutputBundle {
+            results: vec![detection(Some(Suppression {
+                directive_line: 1,
+                justification: Some("synthetic fixture".to_string()),
+                rejected: false,
+            }))],
+            summary: ResultsSummary::default(),
+        };
+        let sarif = SarifFormatter.to_value(&bundle);
+        let suppression = &sarif["runs"][0]["results"][0]["suppressions"][0];
+        assert_eq!(suppression["kind"], "inSource");
+        assert_eq!(suppression["justification"], "synthetic fixture");
+    }
+}
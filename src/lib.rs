@@ -3,17 +3,33 @@
 pub mod domain;
 pub mod error;
 
+pub mod allowlist;
+pub mod baseline;
+pub mod config;
+pub mod content;
+pub mod encoding;
 pub mod file_source;
+pub mod fingerprint;
+pub mod format;
 pub mod phi_patterns;
 pub mod redactor;
 pub mod results;
+pub mod rules;
 pub mod scanner;
+pub mod suppression;
 
+pub use allowlist::Allowlist;
+pub use baseline::{Baseline, BaselineEntry};
+pub use config::{PatternCatalogue, load_yaml};
 pub use domain::{
-    Category, DetectorType, Finding, MatchSpan, Pattern, PatternId, RedactionStrategy, Score,
-    Severity, UuPdpArticle,
+    Category, DetectorType, Finding, MatchSpan, Pattern, PatternId, RedactionStrategy, RegexLimits,
+    Score, Severity, Suppression, UuPdpArticle,
 };
 pub use error::{HealthwandError, Result};
-pub use file_source::{FileSource, LocalFileSource};
+pub use file_source::{
+    Entry, FileId, FileSource, Listing, LocalFileSource, MemoryFileSource, SkipReason, SkippedFile,
+};
+pub use format::{Formatter, SarifFormatter};
 pub use redactor::*;
 pub use results::{DetectionResult, OutputBundle, ResultsSummary};
+pub use rules::{PathRule, RuleSet};
@@ -1,217 +1,412 @@
-use regex::Regex;
+use crate::config::validate::CompiledRegexes;
+use crate::domain::{
+    Category, DetectorType, Escalation, EscalationRule, Pattern, PatternId, RedactionStrategy,
+    RegexLimits, Score, Severity, UuPdpArticle,
+};
+use crate::error::HealthwandError;
 use serde::{Deserialize, Serialize};
-use std::io::BufReader;
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum PHIType {
-    SSN,
-    MedicalRecordNumber,
-    ICD10,
-    DateOfBirth,
-    IndonesianNIK,
-    IndonesianBPJS,
-    // Add more as needed
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PatternContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub before: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub after: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub window: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Redaction settings. Any field may be left out and filled from `defaults`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Redaction {
-    pub template: String,
-    pub strategy: String, // Could be enum if you want strong typing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
+    /// What partial masking leaves visible: `last:<n>` (default `last:4`),
+    /// `first:<n>` or `year`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<String>,
+}
+
+impl Redaction {
+    /// Map the YAML strategy name onto the domain `RedactionStrategy` (`full` if unset).
+    pub fn to_strategy(&self) -> crate::error::Result<RedactionStrategy> {
+        match self
+            .strategy
+            .as_deref()
+            .unwrap_or("full")
+            .to_lowercase()
+            .as_str()
+        {
+            "full" => Ok(RedactionStrategy::FullReplacement),
+            "partial" => Ok(RedactionStrategy::PartialMasking),
+            "placeholder" | "custom" => Ok(RedactionStrategy::PlaceholderSubstitution),
+            other => Err(HealthwandError::ConfigError(format!(
+                "unknown redaction strategy: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Context-driven severity rules, as written in YAML.
+///
+/// ```yaml
+/// escalation:
+///   window: 80
+///   rules:
+///     - context_words: ["diagnosis", "kode icd"]
+///       patterns: ["indonesian_nik", "indonesian_bpjs"]
+///       severity: "critical"
+///   otherwise: "informational"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<usize>,
+    #[serde(default)]
+    pub rules: Vec<EscalationRuleConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub otherwise: Option<String>,
 }
 
+/// One escalation rule: fires when any context word or co-located pattern appears.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationRuleConfig {
+    #[serde(default)]
+    pub context_words: Vec<String>,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    pub severity: String,
+}
+
+impl EscalationConfig {
+    /// Parse into the domain `Escalation`, rejecting unknown severities and rules with no signals.
+    pub fn to_escalation(&self) -> crate::error::Result<Escalation> {
+        let rules = self
+            .rules
+            .iter()
+            .map(|rule| {
+                if rule.context_words.is_empty() && rule.patterns.is_empty() {
+                    return Err(HealthwandError::ConfigError(
+                        "escalation rule needs at least one of context_words or patterns"
+                            .to_string(),
+                    ));
+                }
+                Ok(EscalationRule {
+                    context_words: rule.context_words.clone(),
+                    co_located: rule
+                        .patterns
+                        .iter()
+                        .map(|id| PatternId::new(id.clone()))
+                        .collect::<crate::error::Result<_>>()?,
+                    severity: parse_severity(&rule.severity)?,
+                })
+            })
+            .collect::<crate::error::Result<_>>()?;
+        Ok(Escalation {
+            window: self.window,
+            rules,
+            otherwise: self.otherwise.as_deref().map(parse_severity).transpose()?,
+        })
+    }
+}
+
+fn parse_severity(s: &str) -> crate::error::Result<Severity> {
+    s.parse().map_err(HealthwandError::ConfigError)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PatternMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub severity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub examples: Option<Vec<String>>,
+    /// Text that must not match; checked by `healthwand validate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub negative_examples: Option<Vec<String>>,
+    /// UU PDP article as cited, e.g. `"4(1)"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uu_pdp_article: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PHIPatternConfig {
     pub id: String,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub regex: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<PatternContext>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub confidence: Option<f32>,
+    #[serde(default)]
     pub redaction: Redaction,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<PatternMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub escalation: Option<EscalationConfig>,
 }
 
 impl PHIPatternConfig {
+    /// Convert a catalogue entry into a validated domain `Pattern`.
+    ///
+    /// Errors name the pattern ID so a bad entry can be found in a large catalogue.
+    pub fn into_pattern(self) -> crate::error::Result<Pattern> {
+        self.into_pattern_with_limits(&RegexLimits::default())
+    }
+
+    /// Like [`into_pattern`](Self::into_pattern), compiling the regex within `limits`.
+    pub fn into_pattern_with_limits(self, limits: &RegexLimits) -> crate::error::Result<Pattern> {
+        self.into_pattern_compiled(&mut CompiledRegexes::new(*limits))
+    }
+
+    /// Like [`into_pattern`](Self::into_pattern), taking the regex from `regexes`.
+    pub(crate) fn into_pattern_compiled(
+        self,
+        regexes: &mut CompiledRegexes,
+    ) -> crate::error::Result<Pattern> {
+        let id = self.id.clone();
+        self.build_pattern(regexes).map_err(|e| match e {
+            // Already names the pattern.
+            e @ HealthwandError::RegexError { .. } => e,
+            e => HealthwandError::ConfigError(format!("pattern '{}': {}", id, e)),
+        })
+    }
+
+    fn build_pattern(self, regexes: &mut CompiledRegexes) -> crate::error::Result<Pattern> {
+        let metadata = self.metadata.as_ref();
+        let severity = metadata
+            .and_then(|m| m.severity.as_deref())
+            .map(parse_severity)
+            .transpose()?
+            .unwrap_or(Severity::Medium);
+        let category = metadata
+            .and_then(|m| m.category.as_deref())
+            .map_or(Category::Other("unknown".to_string()), Category::from_label);
+        let uu_pdp_article = metadata
+            .and_then(|m| m.uu_pdp_article.as_deref())
+            .map(|a| a.parse::<UuPdpArticle>())
+            .transpose()
+            .map_err(HealthwandError::ConfigError)?;
+        let (context_words, context_window) = match &self.context {
+            Some(ctx) => (
+                ctx.before
+                    .iter()
+                    .chain(ctx.after.iter())
+                    .flatten()
+                    .cloned()
+                    .collect(),
+                ctx.window.unwrap_or(0) as usize,
+            ),
+            None => (Vec::new(), 0),
+        };
+        let regex = regexes.compile(&self.id, &self.regex)?;
+        Pattern {
+            id: PatternId::new(self.id)?,
+            name: self.name,
+            detector_type: DetectorType::Regex,
+            category,
+            default_severity: severity,
+            score: Score::new(self.confidence.unwrap_or(1.0))?,
+            redaction_strategy: Some(self.redaction.to_strategy()?),
+            partial_mask: self
+                .redaction
+                .mask
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(HealthwandError::ConfigError)?
+                .unwrap_or_default(),
+            redaction_template: Some(
+                self.redaction
+                    .template
+                    .unwrap_or_else(|| "[REDACTED]".to_string()),
+            ),
+            regex: Some(regex),
+            context_words,
+            context_window,
+            escalation: self
+                .escalation
+                .as_ref()
+                .map(EscalationConfig::to_escalation)
+                .transpose()?,
+            uu_pdp_article,
+        }
+        .validated()
+    }
+
+    /// Read and validate a catalogue file without resolving defaults or overrides.
     pub fn from_yaml_file<P: AsRef<std::path::Path>>(
         path: P,
-    ) -> Result<PHIPatternsFile, Box<dyn std::error::Error>> {
-        let file = std::fs::File::open(path)?;
-        let reader = BufReader::new(file);
-        let patterns_file: PHIPatternsFile = serde_yaml_ng::from_reader(reader)?;
-        Ok(patterns_file)
+    ) -> crate::error::Result<PHIPatternsFile> {
+        let path = path.as_ref();
+        crate::config::parse_document(
+            &std::fs::read(path)?,
+            &path.display().to_string(),
+            &mut CompiledRegexes::new(RegexLimits::default()),
+        )
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PHIPatternsFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<crate::config::SchemaVersion>,
+    #[serde(default)]
     pub patterns: Vec<PHIPatternConfig>,
-    pub defaults: Option<serde_yaml_ng::Value>,
-    pub overrides: Option<serde_yaml_ng::Value>,
+    #[serde(default, skip_serializing_if = "PatternDefaults::is_empty")]
+    pub defaults: PatternDefaults,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, PatternOverride>,
 }
 
-#[derive(Debug, Clone)]
-pub struct PHIPattern {
-    pub phi_type: PHIType,
-    pub regex: Regex,
-    pub context: Option<PatternContext>,
-    pub confidence: Option<f32>,
-    pub redaction: Redaction,
-    pub metadata: Option<PatternMetadata>,
-}
-
-impl PHIPattern {
-    pub fn all_patterns() -> Vec<PHIPattern> {
-        vec![
-            PHIPattern {
-                phi_type: PHIType::SSN,
-                regex: Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap(),
-                context: None,
-                confidence: None,
-                redaction: Redaction {
-                    template: "[REDACTED]".to_string(),
-                    strategy: "full".to_string(),
-                },
-                metadata: None,
-            },
-            PHIPattern {
-                phi_type: PHIType::MedicalRecordNumber,
-                // Example: 8-12 digit numeric, can be adjusted for real-world formats
-                regex: Regex::new(r"\b\d{8,12}\b").unwrap(),
-                context: None,
-                confidence: None,
-                redaction: Redaction {
-                    template: "[REDACTED]".to_string(),
-                    strategy: "full".to_string(),
-                },
-                metadata: None,
-            },
-            PHIPattern {
-                phi_type: PHIType::ICD10,
-                // ICD-10: Letter, 2 digits, optional "." and 1-4 more alphanums
-                regex: Regex::new(r"\b[A-TV-Z][0-9]{2}(\.[A-Z0-9]{1,4})?\b").unwrap(),
-                context: None,
-                confidence: None,
-                redaction: Redaction {
-                    template: "[REDACTED]".to_string(),
-                    strategy: "full".to_string(),
-                },
-                metadata: None,
-            },
-            PHIPattern {
-                phi_type: PHIType::DateOfBirth,
-                // MM/DD/YYYY or YYYY-MM-DD or DD/MM/YYYY (basic)
-                regex: Regex::new(r"\b(\d{2}/\d{2}/\d{4}|\d{4}-\d{2}-\d{2}|\d{2}/\d{2}/\d{4})\b")
-                    .unwrap(),
-                context: None,
-                confidence: None,
-                redaction: Redaction {
-                    template: "[REDACTED]".to_string(),
-                    strategy: "full".to_string(),
-                },
-                metadata: None,
-            },
-            PHIPattern {
-                phi_type: PHIType::IndonesianNIK,
-                // NIK: 16 digits, usually no separators
-                regex: Regex::new(r"\b\d{16}\b").unwrap(),
-                context: None,
-                confidence: None,
-                redaction: Redaction {
-                    template: "[REDACTED]".to_string(),
-                    strategy: "full".to_string(),
-                },
-                metadata: None,
-            },
-            PHIPattern {
-                phi_type: PHIType::IndonesianBPJS,
-                // BPJS: 13 digits
-                regex: Regex::new(r"\b\d{13}\b").unwrap(),
-                context: None,
-                confidence: None,
-                redaction: Redaction {
-                    template: "[REDACTED]".to_string(),
-                    strategy: "full".to_string(),
-                },
-                metadata: None,
-            },
-        ]
+impl PHIPatternsFile {
+    /// Apply `overrides` and `defaults` to the patterns, dropping disabled ones.
+    ///
+    /// Overrides win over values written in the pattern, which win over defaults.
+    /// An override for an ID that is not in the catalogue is an error, so typos do
+    /// not silently leave a pattern untuned.
+    pub fn resolve(self) -> crate::error::Result<Vec<PHIPatternConfig>> {
+        if let Some(unknown) = self
+            .overrides
+            .keys()
+            .find(|id| !self.patterns.iter().any(|p| &p.id == *id))
+        {
+            return Err(HealthwandError::ConfigError(format!(
+                "override for unknown pattern '{}'",
+                unknown
+            )));
+        }
+        let mut resolved = Vec::with_capacity(self.patterns.len());
+        for mut pattern in self.patterns {
+            if let Some(patch) = self.overrides.get(&pattern.id) {
+                if patch.enabled == Some(false) {
+                    continue;
+                }
+                patch.apply(&mut pattern);
+            }
+            self.defaults.apply(&mut pattern);
+            resolved.push(pattern);
+        }
+        Ok(resolved)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_ssn_pattern() {
-        let pat = &PHIPattern::all_patterns()[0];
-        assert_eq!(pat.phi_type, PHIType::SSN);
-        assert!(pat.regex.is_match("123-45-6789"));
-        assert!(!pat.regex.is_match("123456789"));
-    }
+/// Context settings that can be defaulted or overridden.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContextSettings {
+    pub window: Option<u32>,
+}
 
-    #[test]
-    fn test_medical_record_pattern() {
-        let pat = &PHIPattern::all_patterns()[1];
-        assert_eq!(pat.phi_type, PHIType::MedicalRecordNumber);
-        assert!(pat.regex.is_match("12345678"));
-        assert!(pat.regex.is_match("123456789012"));
-        assert!(!pat.regex.is_match("1234"));
-        assert!(!pat.regex.is_match("abc12345678"));
-    }
+/// Values applied to every pattern that leaves them out.
+///
+/// ```yaml
+/// defaults:
+///   confidence: 0.8
+///   severity: "medium"
+///   redaction: { template: "[REDACTED]", strategy: "full" }
+///   context: { window: 5 }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PatternDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redaction: Option<Redaction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<ContextSettings>,
+}
 
-    #[test]
-    fn test_icd10_pattern() {
-        let pat = &PHIPattern::all_patterns()[2];
-        assert_eq!(pat.phi_type, PHIType::ICD10);
-        assert!(pat.regex.is_match("A12"));
-        assert!(pat.regex.is_match("B99.8"));
-        assert!(pat.regex.is_match("C01.123"));
-        assert!(!pat.regex.is_match("123-45-6789"));
+impl PatternDefaults {
+    pub fn is_empty(&self) -> bool {
+        self.confidence.is_none()
+            && self.severity.is_none()
+            && self.redaction.is_none()
+            && self.context.is_none()
     }
 
-    #[test]
-    fn test_date_of_birth_pattern() {
-        let pat = &PHIPattern::all_patterns()[3];
-        assert_eq!(pat.phi_type, PHIType::DateOfBirth);
-        assert!(pat.regex.is_match("12/31/2000"));
-        assert!(pat.regex.is_match("2000-12-31"));
-        assert!(pat.regex.is_match("31/12/2000"));
-        assert!(!pat.regex.is_match("20001231"));
+    fn apply(&self, pattern: &mut PHIPatternConfig) {
+        if pattern.confidence.is_none() {
+            pattern.confidence = self.confidence;
+        }
+        if let Some(severity) = &self.severity {
+            let metadata = pattern.metadata.get_or_insert_with(Default::default);
+            metadata.severity.get_or_insert_with(|| severity.clone());
+        }
+        if let Some(redaction) = &self.redaction {
+            if pattern.redaction.template.is_none() {
+                pattern.redaction.template = redaction.template.clone();
+            }
+            if pattern.redaction.strategy.is_none() {
+                pattern.redaction.strategy = redaction.strategy.clone();
+            }
+            if pattern.redaction.mask.is_none() {
+                pattern.redaction.mask = redaction.mask.clone();
+            }
+        }
+        if let Some(window) = self.context.as_ref().and_then(|c| c.window) {
+            let context = pattern.context.get_or_insert_with(Default::default);
+            context.window.get_or_insert(window);
+        }
     }
+}
 
-    #[test]
-    fn test_indonesian_nik_pattern() {
-        let pat = &PHIPattern::all_patterns()[4];
-        assert_eq!(pat.phi_type, PHIType::IndonesianNIK);
-        assert!(pat.regex.is_match("1234567890123456"));
-        assert!(!pat.regex.is_match("123456789012345"));
-        assert!(!pat.regex.is_match("12345678901234567"));
-        assert!(!pat.regex.is_match("1234-5678-9012-3456"));
-    }
+/// Patch for one pattern, keyed by its ID under `overrides`.
+///
+/// ```yaml
+/// overrides:
+///   indonesian_bpjs: { severity: "critical" }
+///   icd10: { enabled: false }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PatternOverride {
+    pub enabled: Option<bool>,
+    pub confidence: Option<f32>,
+    pub severity: Option<String>,
+    pub category: Option<String>,
+    pub redaction: Option<Redaction>,
+    pub context: Option<ContextSettings>,
+}
 
-    #[test]
-    fn test_indonesian_bpjs_pattern() {
-        let pat = &PHIPattern::all_patterns()[5];
-        assert_eq!(pat.phi_type, PHIType::IndonesianBPJS);
-        assert!(pat.regex.is_match("1234567890123"));
-        assert!(!pat.regex.is_match("123456789012"));
-        assert!(!pat.regex.is_match("12345678901234"));
-        assert!(!pat.regex.is_match("BPJS1234567890"));
+impl PatternOverride {
+    fn apply(&self, pattern: &mut PHIPatternConfig) {
+        if self.confidence.is_some() {
+            pattern.confidence = self.confidence;
+        }
+        if self.severity.is_some() || self.category.is_some() {
+            let metadata = pattern.metadata.get_or_insert_with(Default::default);
+            if self.severity.is_some() {
+                metadata.severity = self.severity.clone();
+            }
+            if self.category.is_some() {
+                metadata.category = self.category.clone();
+            }
+        }
+        if let Some(redaction) = &self.redaction {
+            if redaction.template.is_some() {
+                pattern.redaction.template = redaction.template.clone();
+            }
+            if redaction.strategy.is_some() {
+                pattern.redaction.strategy = redaction.strategy.clone();
+            }
+            if redaction.mask.is_some() {
+                pattern.redaction.mask = redaction.mask.clone();
+            }
+        }
+        if let Some(window) = self.context.as_ref().and_then(|c| c.window) {
+            pattern.context.get_or_insert_with(Default::default).window = Some(window);
+        }
     }
 }
 
@@ -239,17 +434,139 @@ patterns:
         assert_eq!(patterns_file.patterns.len(), 1);
         assert_eq!(patterns_file.patterns[0].id, "ssn");
         assert_eq!(
-            patterns_file.patterns[0].redaction.template,
-            "[REDACTED-SSN]"
+            patterns_file.patterns[0].redaction.template.as_deref(),
+            Some("[REDACTED-SSN]")
         );
     }
 
+    #[test]
+    fn test_escalation_rules_from_yaml() {
+        let yaml = r#"
+patterns:
+  - id: 'icd10'
+    name: 'ICD-10 Code'
+    regex: "\\b[A-TV-Z][0-9]{2}\\b"
+    redaction:
+      template: '[REDACTED-ICD10]'
+      strategy: 'full'
+    escalation:
+      window: 40
+      rules:
+        - context_words: ['diagnosis']
+          patterns: ['indonesian_nik']
+          severity: 'critical'
+      otherwise: 'informational'
+"#;
+        let mut tmp = NamedTempFile::new().expect("Failed to create temp file");
+        write!(tmp, "{}", yaml).unwrap();
+        let patterns_file =
+            PHIPatternConfig::from_yaml_file(tmp.path()).expect("Failed to load YAML");
+        let escalation = patterns_file.patterns[0]
+            .escalation
+            .as_ref()
+            .unwrap()
+            .to_escalation()
+            .unwrap();
+        assert_eq!(escalation.window, Some(40));
+        assert_eq!(escalation.rules[0].severity, Severity::Critical);
+        assert_eq!(escalation.rules[0].co_located[0].as_str(), "indonesian_nik");
+        assert_eq!(escalation.otherwise, Some(Severity::Informational));
+    }
+
+    #[test]
+    fn test_escalation_rule_without_signals_rejected() {
+        let config = EscalationConfig {
+            window: None,
+            rules: vec![EscalationRuleConfig {
+                context_words: vec![],
+                patterns: vec![],
+                severity: "critical".to_string(),
+            }],
+            otherwise: None,
+        };
+        assert!(config.to_escalation().is_err());
+    }
+
     #[test]
     fn test_invalid_yaml() {
         let yaml = "not: valid: yaml";
         let mut tmp = NamedTempFile::new().expect("Failed to create temp file");
         write!(tmp, "{}", yaml).unwrap();
-        let result = PHIPatternConfig::from_yaml_file(tmp.path());
-        assert!(result.is_err());
+        match PHIPatternConfig::from_yaml_file(tmp.path()).unwrap_err() {
+            HealthwandError::YamlError(msg) => {
+                assert!(msg.contains(&tmp.path().display().to_string()))
+            }
+            _ => panic!("Expected YamlError"),
+        }
+    }
+
+    fn parse(yaml: &str) -> PHIPatternsFile {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    const TWO_PATTERNS: &str = r#"
+patterns:
+  - id: "indonesian_bpjs"
+    name: "Indonesian BPJS"
+    regex: "\\b\\d{13}\\b"
+    metadata:
+      severity: "high"
+  - id: "icd10"
+    name: "ICD-10 Code"
+    regex: "\\b[A-TV-Z][0-9]{2}\\b"
+    confidence: 0.85
+    redaction:
+      strategy: "partial"
+defaults:
+  confidence: 0.8
+  severity: "medium"
+  redaction:
+    template: "[REDACTED]"
+    strategy: "full"
+  context:
+    window: 5
+"#;
+
+    #[test]
+    fn test_defaults_fill_missing_values() {
+        let resolved = parse(TWO_PATTERNS).resolve().unwrap();
+        let bpjs = resolved[0].clone().into_pattern().unwrap();
+        assert_eq!(bpjs.score.value(), 0.8);
+        assert_eq!(bpjs.default_severity, Severity::High);
+        assert_eq!(bpjs.context_window, 5);
+        assert_eq!(bpjs.redaction_template.as_deref(), Some("[REDACTED]"));
+        assert_eq!(
+            bpjs.redaction_strategy,
+            Some(RedactionStrategy::FullReplacement)
+        );
+
+        let icd10 = resolved[1].clone().into_pattern().unwrap();
+        assert_eq!(icd10.score.value(), 0.85);
+        assert_eq!(icd10.default_severity, Severity::Medium);
+        assert_eq!(
+            icd10.redaction_strategy,
+            Some(RedactionStrategy::PartialMasking)
+        );
+    }
+
+    #[test]
+    fn test_overrides_raise_severity_and_disable() {
+        let yaml = format!(
+            "{}overrides:\n  indonesian_bpjs: {{ severity: \"critical\" }}\n  icd10: {{ enabled: false }}\n",
+            TWO_PATTERNS
+        );
+        let resolved = parse(&yaml).resolve().unwrap();
+        assert_eq!(resolved.len(), 1);
+        let bpjs = resolved[0].clone().into_pattern().unwrap();
+        assert_eq!(bpjs.default_severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_override_for_unknown_pattern_rejected() {
+        let yaml = format!("{}overrides:\n  nik: {{ enabled: false }}\n", TWO_PATTERNS);
+        match parse(&yaml).resolve().unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("'nik'")),
+            _ => panic!("Expected ConfigError"),
+        }
     }
 }
@@ -1,12 +1,7 @@
-use crate::phi_patterns::PHIType;
-use crate::scanner::Detection;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum RedactionStrategy {
-    FullReplacement,         // e.g., XXX-XX-XXXX
-    PartialMasking,          // e.g., ***-**-1234
-    PlaceholderSubstitution, // e.g., [REDACTED-SSN]
-}
+use crate::domain::{Finding, PartialMask};
+use crate::encoding::DecodedText;
+
+pub use crate::domain::RedactionStrategy;
 
 pub struct Redactor {
     pub strategy: RedactionStrategy,
@@ -17,146 +12,113 @@ impl Redactor {
         Self { strategy }
     }
 
-    pub fn redact(&self, text: &str, detections: &[Detection]) -> String {
+    pub fn redact(&self, text: &str, findings: &[Finding]) -> String {
         let mut redacted = String::with_capacity(text.len());
         let mut last = 0;
-        let mut sorted = detections.to_vec();
-        sorted.sort_by_key(|d| d.start);
+        let mut sorted = findings.to_vec();
+        sorted.sort_by_key(|f| f.span.start);
 
-        for det in sorted {
-            if det.start >= last {
+        for f in sorted {
+            if f.span.start >= last {
                 // Non-overlapping span – redact normally
-                redacted.push_str(&text[last..det.start]);
-                redacted.push_str(&self.redaction_text(&det.phi_type, &det.matched_text));
-                last = det.end;
-            } else if det.end > last {
+                redacted.push_str(&text[last..f.span.start]);
+                redacted.push_str(&self.redaction_text(&f, &f.matched_text));
+                last = f.span.end;
+            } else if f.span.end > last {
                 // Overlaps but extends further – make sure the tail gets redacted
                 redacted.push_str(&self.redaction_text(
-                    &det.phi_type,
+                    &f,
                     // use the uncovered slice for length parity
-                    &det.matched_text[(det.matched_text.len() - (det.end - last))..],
+                    &text[last..f.span.end],
                 ));
-                last = det.end;
+                last = f.span.end;
             }
-            // Skip if this detection overlaps with an already processed one
-            // (start position is before the end of the last processed detection)
+            // Skip if this finding overlaps with an already processed one
+            // (start position is before the end of the last processed finding)
         }
         redacted.push_str(&text[last..]);
         redacted
     }
 
-    /// Returns the redacted string for a given PHI type and matched text, according to the current strategy.
-    pub fn redaction_text(&self, phi_type: &PHIType, matched: &str) -> String {
+    /// Redact decoded file content and encode the result back into the file's
+    /// original encoding, ready to be written over it.
+    pub fn redact_encoded(&self, decoded: &DecodedText, findings: &[Finding]) -> Vec<u8> {
+        decoded.encode(&self.redact(&decoded.text, findings))
+    }
+
+    /// Returns the redacted string for a finding's matched text, according to the current strategy.
+    ///
+    /// Masking strategies keep separators (`-`, `/`, `.`, spaces) so the redacted value
+    /// retains the shape of the original, and partial masking leaves visible what the
+    /// pattern's [`PartialMask`] says; placeholders come from the pattern's template.
+    pub fn redaction_text(&self, finding: &Finding, matched: &str) -> String {
         match self.strategy {
-            RedactionStrategy::FullReplacement => match phi_type {
-                PHIType::SSN => "XXX-XX-XXXX".to_string(),
-                PHIType::MedicalRecordNumber => "X".repeat(matched.len()),
-                PHIType::ICD10 => "X".repeat(matched.len()),
-                PHIType::DateOfBirth => "XX/XX/XXXX".to_string(),
-                PHIType::IndonesianNIK => "XXXXXXXXXXXXXXXX".to_string(),
-                PHIType::IndonesianBPJS => "XXXXXXXXXXXXX".to_string(),
-            },
-            RedactionStrategy::PartialMasking => match phi_type {
-                PHIType::SSN => {
-                    // Preserve SSN format: ***-**-1234
-                    if matched.len() == 11
-                        && matched.chars().nth(3) == Some('-')
-                        && matched.chars().nth(6) == Some('-')
-                    {
-                        format!("***-**-{}", &matched[7..])
-                    } else {
-                        // fallback: mask all but last 4
-                        let len = matched.len();
-                        if len > 4 {
-                            format!("{}{}", "*".repeat(len - 4), &matched[len - 4..])
-                        } else {
-                            "*".repeat(len)
-                        }
-                    }
-                }
-                PHIType::MedicalRecordNumber => {
-                    let len = matched.len();
-                    if len > 4 {
-                        format!("{}{}", "*".repeat(len - 4), &matched[len - 4..])
-                    } else {
-                        "*".repeat(len)
-                    }
-                }
-                PHIType::ICD10 => {
-                    // Keep only the first character visible
-                    if !matched.is_empty() {
-                        format!("{}{}", &matched[0..1], "*".repeat(matched.len() - 1))
-                    } else {
-                        "*".repeat(matched.len())
-                    }
-                }
-                PHIType::DateOfBirth => {
-                    // Show only the year part
-                    if matched.len() >= 4 {
-                        if matched.contains('/') || matched.contains('-') {
-                            let parts: Vec<&str> = matched.split(['/', '-']).collect();
-                            if parts.len() >= 3 {
-                                let year_part = if parts[0].len() == 4 {
-                                    parts[0]
-                                } else {
-                                    parts[2]
-                                };
-                                format!("**/**/{}", year_part)
-                            } else {
-                                "*".repeat(matched.len())
-                            }
-                        } else {
-                            "*".repeat(matched.len())
-                        }
-                    } else {
-                        "*".repeat(matched.len())
-                    }
-                }
-                PHIType::IndonesianNIK => {
-                    if matched.len() > 8 {
-                        format!(
-                            "{}{}",
-                            "*".repeat(matched.len() - 8),
-                            &matched[matched.len() - 8..]
-                        )
-                    } else {
-                        "*".repeat(matched.len())
-                    }
-                }
-                PHIType::IndonesianBPJS => {
-                    if matched.len() > 6 {
-                        format!(
-                            "{}{}",
-                            "*".repeat(matched.len() - 6),
-                            &matched[matched.len() - 6..]
-                        )
-                    } else {
-                        "*".repeat(matched.len())
-                    }
-                }
-            },
-            RedactionStrategy::PlaceholderSubstitution => match phi_type {
-                PHIType::SSN => "[REDACTED-SSN]".to_string(),
-                PHIType::MedicalRecordNumber => "[REDACTED-MRN]".to_string(),
-                PHIType::ICD10 => "[REDACTED-ICD10]".to_string(),
-                PHIType::DateOfBirth => "[REDACTED-DOB]".to_string(),
-                PHIType::IndonesianNIK => "[REDACTED-NIK]".to_string(),
-                PHIType::IndonesianBPJS => "[REDACTED-BPJS]".to_string(),
+            RedactionStrategy::FullReplacement => mask(matched, 'X', |_, _| false),
+            RedactionStrategy::PartialMasking => match finding.partial_mask {
+                PartialMask::Last(n) => mask(matched, '*', |i, total| i + n >= total),
+                PartialMask::First(n) => mask(matched, '*', |i, _| i < n),
+                PartialMask::Year => mask_year(matched),
             },
+            RedactionStrategy::PlaceholderSubstitution => finding
+                .redaction_template
+                .clone()
+                .unwrap_or_else(|| "[REDACTED]".to_string()),
         }
     }
 }
 
+/// Replace alphanumerics with `mask_char`, except those for which `visible(index,
+/// total)` holds. Values with no more alphanumerics than would stay visible are
+/// masked entirely.
+fn mask(matched: &str, mask_char: char, visible: impl Fn(usize, usize) -> bool) -> String {
+    let total = matched.chars().filter(|c| c.is_alphanumeric()).count();
+    let all_visible = (0..total).all(|i| visible(i, total));
+    let mut seen = 0;
+    matched
+        .chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if !all_visible && visible(seen - 1, total) {
+                c
+            } else {
+                mask_char
+            }
+        })
+        .collect()
+}
+
+/// `**/**/<year>` for a date written `dd/mm/yyyy`, `mm/dd/yyyy` or `yyyy-mm-dd`;
+/// anything else is masked entirely.
+fn mask_year(matched: &str) -> String {
+    let parts: Vec<&str> = matched.split(['/', '-']).collect();
+    let year = match parts[..] {
+        [year, _, _] | [_, _, year] if year.len() == 4 => Some(year),
+        _ => None,
+    };
+    match year {
+        Some(year) => format!("**/**/{}", year),
+        None => "*".repeat(matched.chars().count()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::phi_patterns::PHIPattern;
+    use crate::config;
+    use crate::domain::Pattern;
     use crate::scanner::Scanner;
 
+    fn builtin() -> Vec<Pattern> {
+        config::load_default().unwrap().into_patterns()
+    }
+
     #[test]
     fn test_full_replacement() {
         let text = "SSN: 123-45-6789, MRN: 12345678";
-        let scanner = Scanner::new(PHIPattern::all_patterns(), 0);
+        let scanner = Scanner::new(builtin(), 0);
         let detections = scanner.scan(text);
         let redactor = Redactor::new(RedactionStrategy::FullReplacement);
         let result = redactor.redact(text, &detections);
@@ -166,27 +128,62 @@ mod tests {
     #[test]
     fn test_partial_masking() {
         let text = "SSN: 123-45-6789, MRN: 12345678";
-        let scanner = Scanner::new(PHIPattern::all_patterns(), 0);
+        let scanner = Scanner::new(builtin(), 0);
         let detections = scanner.scan(text);
         let redactor = Redactor::new(RedactionStrategy::PartialMasking);
         let result = redactor.redact(text, &detections);
         assert_eq!(result, "SSN: ***-**-6789, MRN: ****5678");
     }
 
+    #[test]
+    fn test_partial_masking_per_pattern() {
+        let text =
+            "NIK 1234567890123456, BPJS 1234567890123, ICD B99.8, DOB 12/31/2000, 2000-12-31";
+        let scanner = Scanner::new(builtin(), 0);
+        let detections = scanner.scan(text);
+        let redactor = Redactor::new(RedactionStrategy::PartialMasking);
+        assert_eq!(
+            redactor.redact(text, &detections),
+            "NIK ********90123456, BPJS *******890123, ICD B**.*, DOB **/**/2000, **/**/2000"
+        );
+    }
+
+    #[test]
+    fn test_partial_mask_parsing() {
+        assert_eq!("last:8".parse(), Ok(PartialMask::Last(8)));
+        assert_eq!("first:1".parse(), Ok(PartialMask::First(1)));
+        assert_eq!("year".parse(), Ok(PartialMask::Year));
+        assert!("middle:2".parse::<PartialMask>().is_err());
+        assert_eq!(PartialMask::default().to_string(), "last:4");
+    }
+
     #[test]
     fn test_placeholder_substitution() {
         let text = "SSN: 123-45-6789, MRN: 12345678";
-        let scanner = Scanner::new(PHIPattern::all_patterns(), 0);
+        let scanner = Scanner::new(builtin(), 0);
         let detections = scanner.scan(text);
         let redactor = Redactor::new(RedactionStrategy::PlaceholderSubstitution);
         let result = redactor.redact(text, &detections);
         assert_eq!(result, "SSN: [REDACTED-SSN], MRN: [REDACTED-MRN]");
     }
 
+    #[test]
+    fn test_redact_encoded_keeps_original_encoding() {
+        let original = b"Pasien: Jos\xE9, SSN: 123-45-6789";
+        let decoded = DecodedText::decode(original, Some(encoding_rs::WINDOWS_1252)).unwrap();
+        let scanner = Scanner::new(builtin(), 0);
+        let detections = scanner.scan(&decoded.text);
+        let redactor = Redactor::new(RedactionStrategy::FullReplacement);
+        assert_eq!(
+            redactor.redact_encoded(&decoded, &detections),
+            b"Pasien: Jos\xE9, SSN: XXX-XX-XXXX"
+        );
+    }
+
     #[test]
     fn test_overlapping_matches() {
         let text = "NIK: 1234567890123456, BPJS: 1234567890123";
-        let scanner = Scanner::new(PHIPattern::all_patterns(), 0);
+        let scanner = Scanner::new(builtin(), 0);
         let detections = scanner.scan(text);
         let redactor = Redactor::new(RedactionStrategy::FullReplacement);
         let result = redactor.redact(text, &detections);
@@ -196,7 +193,7 @@ mod tests {
     #[test]
     fn test_empty_text() {
         let text = "";
-        let scanner = Scanner::new(PHIPattern::all_patterns(), 0);
+        let scanner = Scanner::new(builtin(), 0);
         let detections = scanner.scan(text);
         let redactor = Redactor::new(RedactionStrategy::FullReplacement);
         let result = redactor.redact(text, &detections);
@@ -206,7 +203,7 @@ mod tests {
     #[test]
     fn test_no_detections() {
         let text = "This text contains no PHI patterns.";
-        let empty_detections: Vec<crate::scanner::Detection> = vec![];
+        let empty_detections: Vec<Finding> = vec![];
         let redactor = Redactor::new(RedactionStrategy::FullReplacement);
         let result = redactor.redact(text, &empty_detections);
         assert_eq!(result, text);
@@ -215,7 +212,7 @@ mod tests {
     #[test]
     fn test_detection_at_start() {
         let text = "123-45-6789 is a SSN at the start.";
-        let scanner = Scanner::new(PHIPattern::all_patterns(), 0);
+        let scanner = Scanner::new(builtin(), 0);
         let detections = scanner.scan(text);
         let redactor = Redactor::new(RedactionStrategy::FullReplacement);
         let result = redactor.redact(text, &detections);
@@ -225,7 +222,7 @@ mod tests {
     #[test]
     fn test_detection_at_end() {
         let text = "SSN at the end: 123-45-6789";
-        let scanner = Scanner::new(PHIPattern::all_patterns(), 0);
+        let scanner = Scanner::new(builtin(), 0);
         let detections = scanner.scan(text);
         let redactor = Redactor::new(RedactionStrategy::FullReplacement);
         let result = redactor.redact(text, &detections);
@@ -235,7 +232,7 @@ mod tests {
     #[test]
     fn test_partial_masking_short_mrn() {
         let text = "Short MRN: 1234";
-        let scanner = Scanner::new(PHIPattern::all_patterns(), 0);
+        let scanner = Scanner::new(builtin(), 0);
         let detections = scanner.scan(text);
         let redactor = Redactor::new(RedactionStrategy::PartialMasking);
         let result = redactor.redact(text, &detections);
@@ -1,23 +1,59 @@
-use crate::phi_patterns::PHIType;
+use crate::domain::{PatternId, Severity, Suppression};
+use crate::file_source::{BlobOrigin, SkipReason};
 use serde::{Deserialize, Serialize};
 
 /// Represents a single PHI detection result, suitable for JSON output.
 ///
 /// # Fields
 /// - `file_path`: The file where the detection was found.
-/// - `phi_type`: The type of PHI detected (e.g., SSN, MRN).
+/// - `phi_type`: The type of PHI detected (e.g., SSN, MedicalRecordNumber); see [`phi_type`].
+/// - `pattern_id`: The catalogue ID of the pattern that matched (e.g., `ssn`, `indonesian_nik`).
+/// - `severity`: The finding's severity after context-driven escalation.
 /// - `location`: A tuple (start, end) indicating the byte offsets of the match in the file.
+/// - `line`: Line (1-based) of the start of the match.
 /// - `context`: A snippet of text surrounding the match for context.
 /// - `matched_text`: The exact text that matched the PHI pattern.
 /// - `redacted_text`: The redacted version of the matched text (if redaction is enabled).
+/// - `suppression`: The inline `healthwand:ignore` directive covering this match, if any.
+/// - `fingerprint`: Stable identity of the finding across edits (see [`crate::fingerprint`]).
+/// - `rules`: Names of the path rules that changed the finding's severity or score.
+/// - `origin`: For a history scan, the blob and the commit that first added it.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DetectionResult {
     pub file_path: String,
-    pub phi_type: PHIType,
+    #[serde(default)]
+    pub phi_type: String,
+    pub pattern_id: PatternId,
+    pub severity: Severity,
     pub location: (usize, usize),
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
     pub context: String,
     pub matched_text: String,
     pub redacted_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suppression: Option<Suppression>,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<BlobOrigin>,
+}
+
+/// The `phi_type` reported for findings of pattern `id`: for the built-in patterns,
+/// the type name reported before patterns came from the catalogue; otherwise the ID.
+pub fn phi_type(id: &PatternId) -> String {
+    match id.as_str() {
+        "ssn" => "SSN",
+        "mrn" | "mrn-generic" => "MedicalRecordNumber",
+        "icd10" => "ICD10",
+        "dob" => "DateOfBirth",
+        "indonesian_nik" => "IndonesianNIK",
+        "indonesian_bpjs" => "IndonesianBPJS",
+        other => other,
+    }
+    .to_string()
 }
 
 /// Summary statistics for a PHI detection run.
@@ -25,15 +61,27 @@ pub struct DetectionResult {
 /// # Fields
 /// - `files_processed`: Number of files scanned.
 /// - `total_detections`: Total number of PHI detections.
-/// - `detections_by_type`: Map from PHI type to count of detections.
+/// - `detections_by_type`: Map from `phi_type` to count of detections.
 /// - `redacted_count`: Number of redactions performed.
+/// - `suppressed_count`: Number of detections suppressed by inline directives.
+/// - `allowlisted_count`: Number of matches excluded by the allowlist (not listed in results).
+/// - `baselined_count`: Number of findings already recorded in the baseline (not listed in results).
 /// - `errors`: List of error messages encountered during processing.
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ResultsSummary {
     pub files_processed: usize,
     pub total_detections: usize,
-    pub detections_by_type: std::collections::HashMap<PHIType, usize>,
+    pub detections_by_type: std::collections::HashMap<String, usize>,
     pub redacted_count: usize,
+    #[serde(default)]
+    pub suppressed_count: usize,
+    #[serde(default)]
+    pub allowlisted_count: usize,
+    #[serde(default)]
+    pub baselined_count: usize,
+    /// Files the walker passed over, by reason.
+    #[serde(default)]
+    pub skipped_files: std::collections::BTreeMap<SkipReason, usize>,
     pub errors: Vec<String>,
 }
 
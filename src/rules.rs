@@ -0,0 +1,310 @@
+//! Path-scoped pattern rules.
+//!
+//! A monorepo rarely wants one catalogue applied identically everywhere. Rules,
+//! listed under `rules:` in `.healthwand.yaml` (or a profile), enable, disable or
+//! re-score patterns for files whose path matches a glob:
+//!
+//! ```yaml
+//! rules:
+//!   - name: "no-icd10-in-docs"
+//!     paths: ["docs/**"]
+//!     patterns: [icd10]
+//!     enabled: false
+//!   - paths: ["data/exports/**"]
+//!     patterns: [indonesian_nik]
+//!     severity: critical
+//!   - name: "dictionaries-on-csv-only"
+//!     paths: ["**"]
+//!     except: ["**/*.csv"]
+//!     detector_type: dictionary
+//!     enabled: false
+//! ```
+//!
+//! Globs match the path relative to the repository root (see
+//! [`RuleSet::with_glob_root`]), wherever healthwand runs from. Rules apply
+//! in order, so a later rule can re-enable what an earlier one disabled. A rule's
+//! `severity` replaces the escalated severity and its `score` the pattern's
+//! confidence. Findings list the rules that affected them.
+
+use crate::domain::{DetectorType, Pattern, Score, Severity};
+use crate::error::HealthwandError;
+use crate::file_source::{absolute, relative_to};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One rule as written in a settings file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathRule {
+    /// Name recorded on affected findings; defaults to `rules[N]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Globs of the files the rule applies to.
+    pub paths: Vec<String>,
+    /// Globs of files excluded from `paths`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub except: Vec<String>,
+    /// Pattern IDs in scope; empty means every pattern.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    /// Only patterns of this detector type are in scope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detector_type: Option<DetectorType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+}
+
+impl PathRule {
+    /// The rule's name, or `rules[index]` for an unnamed rule.
+    pub fn label(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("rules[{}]", index))
+    }
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    name: String,
+    paths: GlobSet,
+    except: GlobSet,
+    patterns: Vec<String>,
+    detector_type: Option<DetectorType>,
+    enabled: Option<bool>,
+    severity: Option<Severity>,
+    score: Option<Score>,
+}
+
+impl CompiledRule {
+    fn covers(&self, pattern: &Pattern) -> bool {
+        (self.patterns.is_empty() || self.patterns.iter().any(|id| id == pattern.id.as_str()))
+            && self
+                .detector_type
+                .is_none_or(|t| t == pattern.detector_type)
+    }
+}
+
+/// Compiled path rules.
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+    glob_root: PathBuf,
+}
+
+impl RuleSet {
+    /// Compile `rules`, naming the offending rule in any error.
+    pub fn new(rules: &[PathRule]) -> crate::error::Result<Self> {
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let name = rule.label(i);
+                compile(rule, name.clone()).map_err(|e| match e {
+                    HealthwandError::ConfigError(msg) => {
+                        HealthwandError::ConfigError(format!("rule '{}': {}", name, msg))
+                    }
+                    other => other,
+                })
+            })
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        Ok(Self {
+            rules,
+            glob_root: PathBuf::from("."),
+        })
+    }
+
+    /// Directory the globs are relative to (the repository root); defaults to the
+    /// working directory.
+    pub fn with_glob_root<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.glob_root = absolute(dir.as_ref());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rules whose globs match `path`.
+    pub fn for_path(&self, path: &Path) -> FileRules<'_> {
+        let path = relative_to(&self.glob_root, path);
+        FileRules {
+            rules: self
+                .rules
+                .iter()
+                .filter(|r| r.paths.is_match(&path) && !r.except.is_match(&path))
+                .collect(),
+        }
+    }
+}
+
+/// The rules that apply to one file.
+#[derive(Debug, Default)]
+pub struct FileRules<'a> {
+    rules: Vec<&'a CompiledRule>,
+}
+
+/// The combined effect of the rules on one pattern in one file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternRules {
+    pub enabled: bool,
+    pub severity: Option<Severity>,
+    pub score: Option<Score>,
+    /// Names of the rules that changed something, in order.
+    pub applied: Vec<String>,
+}
+
+impl FileRules<'_> {
+    pub fn for_pattern(&self, pattern: &Pattern) -> PatternRules {
+        let mut effect = PatternRules {
+            enabled: true,
+            severity: None,
+            score: None,
+            applied: Vec::new(),
+        };
+        for rule in self.rules.iter().filter(|r| r.covers(pattern)) {
+            if let Some(enabled) = rule.enabled {
+                effect.enabled = enabled;
+            }
+            effect.severity = rule.severity.or(effect.severity);
+            effect.score = rule.score.or(effect.score);
+            effect.applied.push(rule.name.clone());
+        }
+        effect
+    }
+}
+
+fn compile(rule: &PathRule, name: String) -> crate::error::Result<CompiledRule> {
+    if rule.paths.is_empty() {
+        return Err(HealthwandError::ConfigError(
+            "paths must list at least one glob".to_string(),
+        ));
+    }
+    if rule.enabled.is_none() && rule.severity.is_none() && rule.score.is_none() {
+        return Err(HealthwandError::ConfigError(
+            "rule must set enabled, severity or score".to_string(),
+        ));
+    }
+    let score = rule.score.map(Score::new).transpose()?;
+    Ok(CompiledRule {
+        name,
+        paths: glob_set(&rule.paths)?,
+        except: glob_set(&rule.except)?,
+        patterns: rule.patterns.clone(),
+        detector_type: rule.detector_type,
+        enabled: rule.enabled,
+        severity: rule.severity,
+        score,
+    })
+}
+
+fn glob_set(globs: &[String]) -> crate::error::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).map_err(|e| HealthwandError::ConfigError(e.to_string()))?);
+    }
+    builder
+        .build()
+        .map_err(|e| HealthwandError::ConfigError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(yaml: &str) -> RuleSet {
+        let rules: Vec<PathRule> = serde_yaml_ng::from_str(yaml).unwrap();
+        RuleSet::new(&rules).unwrap()
+    }
+
+    fn pattern(id: &str) -> Pattern {
+        crate::config::load_default()
+            .unwrap()
+            .get(id)
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_rules_apply_in_order_by_path() {
+        let set = rules(
+            r#"
+- name: "no-icd10-in-docs"
+  paths: ["docs/**"]
+  patterns: [icd10]
+  enabled: false
+- paths: ["docs/clinical/**"]
+  patterns: [icd10]
+  enabled: true
+- paths: ["data/exports/**"]
+  patterns: [indonesian_nik]
+  severity: critical
+  score: 1.0
+"#,
+        );
+        let icd10 = pattern("icd10");
+        assert!(
+            !set.for_path(Path::new("./docs/a.md"))
+                .for_pattern(&icd10)
+                .enabled
+        );
+        let clinical = set
+            .for_path(Path::new("docs/clinical/b.md"))
+            .for_pattern(&icd10);
+        assert!(clinical.enabled);
+        assert_eq!(clinical.applied, vec!["no-icd10-in-docs", "rules[1]"]);
+
+        let nik = pattern("indonesian_nik");
+        let export = set
+            .for_path(Path::new("data/exports/2024.csv"))
+            .for_pattern(&nik);
+        assert_eq!(export.severity, Some(Severity::Critical));
+        assert_eq!(export.score.map(Score::value), Some(1.0));
+        assert!(
+            set.for_path(Path::new("data/exports/x.csv"))
+                .for_pattern(&icd10)
+                .applied
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_except_and_detector_type() {
+        let set = rules(
+            r#"
+- paths: ["**"]
+  except: ["**/*.csv"]
+  detector_type: regex
+  enabled: false
+"#,
+        );
+        let ssn = pattern("ssn");
+        assert!(
+            !set.for_path(Path::new("notes/a.txt"))
+                .for_pattern(&ssn)
+                .enabled
+        );
+        assert!(
+            set.for_path(Path::new("data/a.csv"))
+                .for_pattern(&ssn)
+                .enabled
+        );
+    }
+
+    #[test]
+    fn test_invalid_rule_is_named() {
+        let rules: Vec<PathRule> =
+            serde_yaml_ng::from_str("- name: \"broken\"\n  paths: [\"a/**\"]\n  score: 1.5\n")
+                .unwrap();
+        match RuleSet::new(&rules).unwrap_err() {
+            HealthwandError::ConfigError(msg) => assert!(msg.contains("rule 'broken'")),
+            _ => panic!("Expected ConfigError"),
+        }
+        let noop: Vec<PathRule> = serde_yaml_ng::from_str("- paths: [\"a/**\"]\n").unwrap();
+        assert!(RuleSet::new(&noop).is_err());
+    }
+}
@@ -1,73 +1,253 @@
-use crate::phi_patterns::{PHIPattern, PHIType};
-
-#[derive(Debug, Clone)]
-pub struct Detection {
-    pub matched_text: String,
-    pub start: usize,
-    pub end: usize,
-    pub phi_type: PHIType,
-    pub confidence: f32,
-    pub context: String,
-}
+use crate::domain::escalation::contains_word;
+use crate::domain::{Finding, LineIndex, Pattern, PatternId, Score, SpanIndex};
+use crate::fingerprint;
+use crate::rules::{PatternRules, RuleSet};
+use crate::suppression;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Former name of [`Finding`], kept so existing callers still compile.
+pub type Detection = Finding;
 
 pub struct Scanner {
-    patterns: Vec<PHIPattern>,
+    patterns: Vec<Pattern>,
     context_window: usize,
+    strict_suppressions: bool,
+    fingerprint_key: Vec<u8>,
+    min_score: Option<Score>,
+    rules: RuleSet,
+    /// One counter per pattern, when timing is enabled.
+    timings: Option<Vec<Counter>>,
+}
+
+#[derive(Debug, Default)]
+struct Counter {
+    nanos: AtomicU64,
+    scans: AtomicU64,
+    matches: AtomicU64,
+}
+
+/// Time spent in one pattern across every scan, for `--profile-patterns`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternTiming {
+    pub pattern_id: PatternId,
+    /// Texts the pattern ran over.
+    pub scans: u64,
+    pub matches: u64,
+    pub total: Duration,
 }
 
 impl Scanner {
-    pub fn new(patterns: Vec<PHIPattern>, context_window: usize) -> Self {
+    pub fn new(patterns: Vec<Pattern>, context_window: usize) -> Self {
         Self {
             patterns,
             context_window,
+            strict_suppressions: false,
+            fingerprint_key: fingerprint::DEFAULT_KEY.to_vec(),
+            min_score: None,
+            rules: RuleSet::default(),
+            timings: None,
         }
     }
 
-    pub fn scan(&self, text: &str) -> Vec<Detection> {
-        let mut detections = Vec::new();
-        for pat in &self.patterns {
-            for mat in pat.regex.find_iter(text) {
-                let start = mat.start();
-                let end = mat.end();
-                let matched_text = mat.as_str().to_string();
-                let context = Self::extract_context(text, start, end, self.context_window);
-                let confidence = 1.0; // Placeholder, can be refined
-                detections.push(Detection {
-                    matched_text,
-                    start,
-                    end,
-                    phi_type: pat.phi_type.clone(),
-                    confidence,
-                    context,
-                });
-            }
-        }
-        detections
+    /// Reject inline `healthwand:ignore` directives that carry no justification.
+    pub fn with_strict_suppressions(mut self, strict: bool) -> Self {
+        self.strict_suppressions = strict;
+        self
     }
 
-    fn extract_context(text: &str, start: usize, end: usize, window: usize) -> String {
-        let mut left = start.saturating_sub(window);
-        let mut right = usize::min(text.len(), end + window);
+    /// Key for the keyed hash in finding fingerprints.
+    pub fn with_fingerprint_key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.fingerprint_key = key.into();
+        self
+    }
+
+    /// Drop findings whose score is below `min`.
+    pub fn with_min_score(mut self, min: Score) -> Self {
+        self.min_score = Some(min);
+        self
+    }
+
+    /// Path rules consulted by [`Scanner::scan_path`].
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Count the time each pattern spends matching; read with [`Scanner::pattern_timings`].
+    pub fn with_pattern_timings(mut self, enabled: bool) -> Self {
+        self.timings = enabled.then(|| self.patterns.iter().map(|_| Counter::default()).collect());
+        self
+    }
 
-        while left > 0 && !text.is_char_boundary(left) {
-            left -= 1;
+    /// Per-pattern timings so far, slowest first; empty unless enabled.
+    pub fn pattern_timings(&self) -> Vec<PatternTiming> {
+        let mut timings: Vec<PatternTiming> = self
+            .patterns
+            .iter()
+            .zip(self.timings.iter().flatten())
+            .map(|(pat, counter)| PatternTiming {
+                pattern_id: pat.id.clone(),
+                scans: counter.scans.load(Ordering::Relaxed),
+                matches: counter.matches.load(Ordering::Relaxed),
+                total: Duration::from_nanos(counter.nanos.load(Ordering::Relaxed)),
+            })
+            .collect();
+        timings.sort_by_key(|t| std::cmp::Reverse(t.total));
+        timings
+    }
+
+    /// Scan `text` with every pattern, then resolve each finding's severity, apply
+    /// inline suppression directives and compute fingerprints.
+    ///
+    /// Severity is resolved in a second pass because escalation rules can depend on
+    /// which other patterns matched nearby.
+    pub fn scan(&self, text: &str) -> Vec<Finding> {
+        self.scan_with(text, |_| None)
+    }
+
+    /// Scan the contents of the file at `path`, applying the path rules that match it:
+    /// disabled patterns do not run, and rule severities and scores replace the
+    /// pattern's.
+    pub fn scan_path(&self, path: &Path, text: &str) -> Vec<Finding> {
+        if self.rules.is_empty() {
+            return self.scan(text);
+        }
+        let file_rules = self.rules.for_path(path);
+        self.scan_with(text, |pat| Some(file_rules.for_pattern(pat)))
+    }
+
+    fn scan_with(
+        &self,
+        text: &str,
+        rules_for: impl Fn(&Pattern) -> Option<PatternRules>,
+    ) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut owners = Vec::new();
+        let lines = LineIndex::new(text);
+        for (i, pat) in self.patterns.iter().enumerate() {
+            let Some(regex) = &pat.regex else {
+                continue;
+            };
+            let rules = rules_for(pat);
+            if rules.as_ref().is_some_and(|r| !r.enabled) {
+                continue;
+            }
+            let started = Instant::now();
+            let before = findings.len();
+            // A `value` group narrows the finding to part of the match (e.g. the digits
+            // after an "MRN:" label), so the rest is neither reported nor redacted.
+            let value = regex.capture_names().position(|n| n == Some("value"));
+            let matches: Box<dyn Iterator<Item = regex::Match>> = match value {
+                Some(group) => {
+                    Box::new(regex.captures_iter(text).filter_map(move |c| c.get(group)))
+                }
+                None => Box::new(regex.find_iter(text)),
+            };
+            for mat in matches {
+                let Ok(span) = lines.span(text, mat.start(), mat.end()) else {
+                    continue;
+                };
+                let context = span.surrounding(text, self.context_window).to_string();
+                let lowered = span.surrounding(text, pat.context_window).to_lowercase();
+                let context_matched = pat
+                    .context_words
+                    .iter()
+                    .any(|w| contains_word(&lowered, &w.to_lowercase()));
+                findings.push(Finding {
+                    pattern_id: pat.id.clone(),
+                    span,
+                    matched_text: mat.as_str().to_string(),
+                    severity: pat.default_severity,
+                    score: rules.as_ref().and_then(|r| r.score).unwrap_or(pat.score),
+                    context: Some(context),
+                    context_matched,
+                    uu_pdp_article: pat.uu_pdp_article,
+                    redaction_template: pat.redaction_template.clone(),
+                    redaction_strategy: pat.redaction_strategy,
+                    partial_mask: pat.partial_mask,
+                    suppression: None,
+                    fingerprint: None,
+                    rules: rules
+                        .as_ref()
+                        .map(|r| r.applied.clone())
+                        .unwrap_or_default(),
+                });
+                owners.push((pat, rules.as_ref().and_then(|r| r.severity)));
+            }
+            if let Some(counter) = self.timings.as_ref().map(|t| &t[i]) {
+                let nanos = started.elapsed().as_nanos().min(u64::MAX as u128) as u64;
+                counter.nanos.fetch_add(nanos, Ordering::Relaxed);
+                counter.scans.fetch_add(1, Ordering::Relaxed);
+                counter
+                    .matches
+                    .fetch_add((findings.len() - before) as u64, Ordering::Relaxed);
+            }
         }
 
-        while right < text.len() && !text.is_char_boundary(right) {
-            right += 1;
+        let index = SpanIndex::new(&findings);
+        let severities: Vec<_> = findings
+            .iter()
+            .zip(&owners)
+            .map(|(finding, (pat, fixed))| {
+                fixed.unwrap_or_else(|| pat.resolve_severity(text, finding, &index))
+            })
+            .collect();
+        for (finding, severity) in findings.iter_mut().zip(severities) {
+            finding.severity = severity;
+        }
+        // After escalation, which may depend on findings that are then dropped.
+        if let Some(min) = self.min_score {
+            findings.retain(|f| f.score >= min);
+        }
+        drop_duplicate_spans(&mut findings);
+        suppression::apply(text, &mut findings, self.strict_suppressions);
+        for finding in &mut findings {
+            finding.fingerprint = Some(fingerprint::fingerprint(
+                &self.fingerprint_key,
+                text,
+                finding,
+            ));
         }
+        findings
+    }
+}
 
-        text[left..right].to_string()
+/// Where several patterns match exactly the same text, keep only the highest-scoring
+/// finding (the earliest pattern on a tie), so one value is reported and redacted once.
+fn drop_duplicate_spans(findings: &mut Vec<Finding>) {
+    let mut best: HashMap<(usize, usize), usize> = HashMap::new();
+    for (i, f) in findings.iter().enumerate() {
+        best.entry((f.span.start, f.span.end))
+            .and_modify(|b| {
+                if f.score > findings[*b].score {
+                    *b = i;
+                }
+            })
+            .or_insert(i);
     }
+    let mut i = 0;
+    findings.retain(|f| {
+        let keep = best[&(f.span.start, f.span.end)] == i;
+        i += 1;
+        keep
+    });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::phi_patterns::{PHIPattern, PHIType};
+    use crate::config;
+    use crate::domain::{Escalation, EscalationRule, PatternId, Severity};
+
+    fn get_test_patterns() -> Vec<Pattern> {
+        config::load_default().unwrap().into_patterns()
+    }
 
-    fn get_test_patterns() -> Vec<PHIPattern> {
-        PHIPattern::all_patterns()
+    fn has(results: &[Finding], id: &str) -> bool {
+        results.iter().any(|d| d.pattern_id.as_str() == id)
     }
 
     #[test]
@@ -75,20 +255,90 @@ mod tests {
         let scanner = Scanner::new(get_test_patterns(), 5);
         let text = "Patient SSN: 123-45-6789. MRN: 123456789. ICD: A12.34. NIK: 1234567890123456. BPJS: 1234567890123. Date: 31/12/2000.";
         let results = scanner.scan(text);
-        assert!(results.iter().any(|d| d.phi_type == PHIType::SSN));
-        assert!(
-            results
-                .iter()
-                .any(|d| d.phi_type == PHIType::MedicalRecordNumber)
-        );
-        assert!(results.iter().any(|d| d.phi_type == PHIType::ICD10));
-        assert!(results.iter().any(|d| d.phi_type == PHIType::IndonesianNIK));
+        assert!(has(&results, "ssn"));
+        assert!(has(&results, "mrn"));
+        assert!(has(&results, "icd10"));
+        assert!(has(&results, "indonesian_nik"));
+        assert!(has(&results, "indonesian_bpjs"));
+        assert!(has(&results, "dob"));
+    }
+
+    #[test]
+    fn test_value_group_narrows_finding_and_same_span_is_reported_once() {
+        let scanner = Scanner::new(get_test_patterns(), 5);
+        let text = "Medical record number 987654, MRN: 12345678";
+        let results = scanner.scan(text);
+        let mrn: Vec<_> = results
+            .iter()
+            .map(|f| (f.pattern_id.as_str(), f.matched_text.as_str()))
+            .collect();
+        assert_eq!(mrn, vec![("mrn", "987654"), ("mrn", "12345678")]);
+        assert!(results.iter().all(|f| f.context_matched));
+    }
+
+    #[test]
+    fn test_min_score_drops_low_confidence_findings() {
+        let text = "SSN 123-45-6789, record 12345678.";
+        let all = Scanner::new(get_test_patterns(), 5).scan(text);
+        let min = Score::new(0.9).unwrap();
+        let confident = Scanner::new(get_test_patterns(), 5)
+            .with_min_score(min)
+            .scan(text);
+        assert!(has(&all, "mrn-generic") && !has(&confident, "mrn-generic"));
+        assert!(has(&confident, "ssn"));
+        assert!(confident.iter().all(|f| f.score >= min));
+    }
+
+    #[test]
+    fn test_scan_path_applies_rules() {
+        let rules: Vec<crate::rules::PathRule> = serde_yaml_ng::from_str(
+            r#"
+- paths: ["docs/**"]
+  patterns: [ssn]
+  enabled: false
+- name: "exports"
+  paths: ["data/exports/**"]
+  patterns: [indonesian_nik]
+  severity: critical
+"#,
+        )
+        .unwrap();
+        let scanner = Scanner::new(get_test_patterns(), 5)
+            .with_rules(crate::rules::RuleSet::new(&rules).unwrap());
+        let text = "SSN 123-45-6789, NIK 3271010101010001";
+
+        let docs = scanner.scan_path(Path::new("docs/notes.md"), text);
+        assert!(!has(&docs, "ssn") && has(&docs, "indonesian_nik"));
+
+        let export = scanner.scan_path(Path::new("data/exports/2024.csv"), text);
+        let nik = export
+            .iter()
+            .find(|f| f.pattern_id.as_str() == "indonesian_nik")
+            .unwrap();
+        assert_eq!(nik.severity, Severity::Critical);
+        assert_eq!(nik.rules, vec!["exports"]);
+        assert!(has(&export, "ssn"));
+        assert!(scanner.scan(text).iter().all(|f| f.rules.is_empty()));
+    }
+
+    #[test]
+    fn test_pattern_timings() {
+        let scanner = Scanner::new(get_test_patterns(), 5).with_pattern_timings(true);
+        scanner.scan("SSN 123-45-6789");
+        scanner.scan("SSN 987-65-4321 and 123-45-6789");
+        let timings = scanner.pattern_timings();
+        assert_eq!(timings.len(), get_test_patterns().len());
+        let ssn = timings
+            .iter()
+            .find(|t| t.pattern_id.as_str() == "ssn")
+            .unwrap();
+        assert_eq!((ssn.scans, ssn.matches), (2, 3));
+        assert!(timings.windows(2).all(|w| w[0].total >= w[1].total));
         assert!(
-            results
-                .iter()
-                .any(|d| d.phi_type == PHIType::IndonesianBPJS)
+            Scanner::new(get_test_patterns(), 5)
+                .pattern_timings()
+                .is_empty()
         );
-        assert!(results.iter().any(|d| d.phi_type == PHIType::DateOfBirth));
     }
 
     #[test]
@@ -98,8 +348,8 @@ mod tests {
         let results = scanner.scan(text);
         for d in &results {
             println!(
-                "Detected: {:?} at {}-{}: {}",
-                d.phi_type, d.start, d.end, d.matched_text
+                "Detected: {} at {}-{}: {}",
+                d.pattern_id, d.span.start, d.span.end, d.matched_text
             );
         }
         assert!(
@@ -107,8 +357,64 @@ mod tests {
             "No PHI detected in test_context_extraction. Patterns: {:?}",
             get_test_patterns()
         );
-        let ssn = results.iter().find(|d| d.phi_type == PHIType::SSN).unwrap();
+        let ssn = results
+            .iter()
+            .find(|d| d.pattern_id.as_str() == "ssn")
+            .unwrap();
         // Should include 3 chars before and after the match
-        assert!(ssn.context.contains("fg 123-45-6789 x"));
+        assert!(ssn.context.as_deref().unwrap().contains("fg 123-45-6789 x"));
+    }
+
+    #[test]
+    fn test_severity_escalates_with_co_located_pattern() {
+        let mut patterns = get_test_patterns();
+        let icd10 = patterns
+            .iter_mut()
+            .find(|p| p.id.as_str() == "icd10")
+            .unwrap();
+        icd10.escalation = Some(Escalation {
+            window: Some(30),
+            rules: vec![EscalationRule {
+                context_words: vec!["diagnosis".to_string()],
+                co_located: vec![PatternId::new("indonesian_nik".to_string()).unwrap()],
+                severity: Severity::Critical,
+            }],
+            otherwise: Some(Severity::Informational),
+        });
+        let scanner = Scanner::new(patterns, 5);
+
+        let clinic = scanner.scan("NIK 1234567890123456 kode A09");
+        let textbook = scanner.scan("Gastroenteritis is coded as A09 in chapter one.");
+        let severity_of = |findings: &[Finding]| {
+            findings
+                .iter()
+                .find(|f| f.pattern_id.as_str() == "icd10")
+                .map(|f| f.severity)
+        };
+        assert_eq!(severity_of(&clinic), Some(Severity::Critical));
+        assert_eq!(severity_of(&textbook), Some(Severity::Informational));
+    }
+
+    #[test]
+    fn test_suppressed_findings_are_kept() {
+        let scanner = Scanner::new(get_test_patterns(), 5);
+        let text = "NIK: 0000000000000000 # healthwand:ignore[indonesian_nik] synthetic";
+        let results = scanner.scan(text);
+        let nik = results
+            .iter()
+            .find(|f| f.pattern_id.as_str() == "indonesian_nik")
+            .unwrap();
+        assert!(nik.is_suppressed());
+    }
+
+    #[test]
+    fn test_findings_carry_fingerprints() {
+        let text = "SSN: 123-45-6789";
+        let default = Scanner::new(get_test_patterns(), 5).scan(text);
+        let keyed = Scanner::new(get_test_patterns(), 5)
+            .with_fingerprint_key("org-key")
+            .scan(text);
+        assert!(default[0].fingerprint.is_some());
+        assert_ne!(default[0].fingerprint, keyed[0].fingerprint);
     }
 }
@@ -0,0 +1,174 @@
+//! Inline suppression directives in scanned files.
+//!
+//! Directives are recognised anywhere on a line, so they work in every comment syntax
+//! (`#`, `//`, `--`, `<!-- -->`, `/* */`, ...):
+//!
+//! - `healthwand:ignore` — suppress findings on the same line.
+//! - `healthwand:ignore-next-line` — suppress findings on the following line.
+//! - `healthwand:ignore[indonesian_nik,ssn]` — only suppress the listed pattern IDs.
+//!
+//! Anything written after the directive is its justification, e.g.
+//! `# healthwand:ignore[indonesian_nik] -- synthetic NIK from taxonomy §0.4`.
+
+use crate::domain::{Finding, Suppression};
+
+const DIRECTIVE: &str = "healthwand:ignore";
+const NEXT_LINE: &str = "-next-line";
+/// Comment terminators stripped from the end of a justification.
+const COMMENT_CLOSERS: [&str; 5] = ["*/", "-->", "--%>", "%>", "#}"];
+
+/// A parsed `healthwand:ignore` directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    /// Line (1-based) the directive is written on.
+    pub line: u32,
+    /// Line (1-based) whose findings the directive suppresses.
+    pub target_line: u32,
+    /// Pattern IDs in scope; empty means every pattern.
+    pub pattern_ids: Vec<String>,
+    pub justification: Option<String>,
+}
+
+impl Directive {
+    fn covers(&self, finding: &Finding, line: u32) -> bool {
+        self.target_line == line
+            && (self.pattern_ids.is_empty()
+                || self
+                    .pattern_ids
+                    .iter()
+                    .any(|id| id == finding.pattern_id.as_str()))
+    }
+}
+
+/// Find every suppression directive in `text`.
+pub fn parse_directives(text: &str) -> Vec<Directive> {
+    text.split('\n')
+        .enumerate()
+        .filter_map(|(idx, line)| parse_line(line, idx as u32 + 1))
+        .collect()
+}
+
+fn parse_line(line: &str, line_no: u32) -> Option<Directive> {
+    let at = line.find(DIRECTIVE)?;
+    let mut rest = &line[at + DIRECTIVE.len()..];
+    let mut target_line = line_no;
+    if let Some(after) = rest.strip_prefix(NEXT_LINE) {
+        rest = after;
+        target_line = line_no + 1;
+    }
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '-' || c == '_') {
+        // e.g. `healthwand:ignored` — not a directive.
+        return None;
+    }
+
+    let mut pattern_ids = Vec::new();
+    if let Some(scoped) = rest.strip_prefix('[') {
+        let close = scoped.find(']')?;
+        pattern_ids = scoped[..close]
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect();
+        rest = &scoped[close + 1..];
+    }
+
+    Some(Directive {
+        line: line_no,
+        target_line,
+        pattern_ids,
+        justification: parse_justification(rest),
+    })
+}
+
+fn parse_justification(rest: &str) -> Option<String> {
+    let mut text = rest.trim().trim_end_matches('\r').trim();
+    for closer in COMMENT_CLOSERS {
+        text = text.strip_suffix(closer).unwrap_or(text).trim_end();
+    }
+    let text = text
+        .trim_start_matches("--")
+        .trim_start_matches([':', '-'])
+        .trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Mark every finding covered by a directive in `text` as suppressed.
+///
+/// In `strict` mode, directives without a justification are recorded on the finding
+/// but rejected, so the finding stays active.
+pub fn apply(text: &str, findings: &mut [Finding], strict: bool) {
+    let directives = parse_directives(text);
+    if directives.is_empty() {
+        return;
+    }
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    for finding in findings.iter_mut() {
+        let line = line_starts.partition_point(|&start| start <= finding.span.start) as u32;
+        if let Some(directive) = directives.iter().find(|d| d.covers(finding, line)) {
+            finding.suppression = Some(Suppression {
+                directive_line: directive.line,
+                justification: directive.justification.clone(),
+                rejected: strict && directive.justification.is_none(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directive_forms() {
+        let text = "a # healthwand:ignore\n\
+                    // healthwand:ignore-next-line[indonesian_nik, ssn] -- synthetic fixture\n\
+                    <!-- healthwand:ignore: documented example -->\n\
+                    healthwand:ignored is not a directive";
+        let directives = parse_directives(text);
+        assert_eq!(directives.len(), 3);
+        assert_eq!(directives[0].target_line, 1);
+        assert_eq!(directives[0].justification, None);
+        assert_eq!(directives[1].target_line, 3);
+        assert_eq!(directives[1].pattern_ids, vec!["indonesian_nik", "ssn"]);
+        assert_eq!(
+            directives[1].justification.as_deref(),
+            Some("synthetic fixture")
+        );
+        assert_eq!(
+            directives[2].justification.as_deref(),
+            Some("documented example")
+        );
+    }
+
+    #[test]
+    fn test_apply_respects_line_and_scope() {
+        let text = "# healthwand:ignore-next-line[indonesian_nik] fake\n\
+                    NIK 0000000000000000 SSN 123-45-6789\n\
+                    NIK 9999999999999999";
+        let mut findings = vec![
+            Finding::for_test("indonesian_nik", text, "0000000000000000"),
+            Finding::for_test("ssn", text, "123-45-6789"),
+            Finding::for_test("indonesian_nik", text, "9999999999999999"),
+        ];
+        apply(text, &mut findings, false);
+        assert!(findings[0].is_suppressed());
+        assert!(!findings[1].is_suppressed());
+        assert!(!findings[2].is_suppressed());
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_unjustified_suppression() {
+        let text = "SSN 123-45-6789 // healthwand:ignore";
+        let mut findings = vec![Finding::for_test("ssn", text, "123-45-6789")];
+        apply(text, &mut findings, true);
+        let suppression = findings[0].suppression.as_ref().unwrap();
+        assert!(suppression.rejected);
+        assert!(!findings[0].is_suppressed());
+
+        apply(text, &mut findings, false);
+        assert!(findings[0].is_suppressed());
+    }
+}
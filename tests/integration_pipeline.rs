@@ -1,12 +1,20 @@
-use healthwand::phi_patterns::PHIType;
-use healthwand::redactor::{RedactionStrategy, Redactor};
-use healthwand::results::{DetectionResult, ResultsSummary};
+use healthwand::config;
+use healthwand::domain::Pattern;
+use healthwand::redactor::Redactor;
+use healthwand::results::{DetectionResult, ResultsSummary, phi_type};
 use healthwand::scanner::Scanner;
+use healthwand::{RedactionStrategy, Severity};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn builtin_patterns() -> Vec<Pattern> {
+    config::load_default().unwrap().into_patterns()
+}
 
 #[test]
 fn test_full_pipeline_json_output() {
     let text = "SSN: 123-45-6789, MRN: 12345678, NIK: 1234567890123456";
-    let scanner = Scanner::new(healthwand::phi_patterns::PHIPattern::all_patterns(), 10);
+    let scanner = Scanner::new(builtin_patterns(), 10);
     let detections = scanner.scan(text);
     let redactor = Redactor::new(RedactionStrategy::FullReplacement);
     let redacted = redactor.redact(text, &detections);
@@ -14,29 +22,40 @@ fn test_full_pipeline_json_output() {
     for det in &detections {
         results.push(DetectionResult {
             file_path: "test.txt".to_string(),
-            phi_type: det.phi_type.clone(),
-            location: (det.start, det.end),
-            context: det.context.clone(),
+            phi_type: phi_type(&det.pattern_id),
+            pattern_id: det.pattern_id.clone(),
+            severity: det.severity,
+            location: (det.span.start, det.span.end),
+            line: Some(det.span.line),
+            context: det.context.clone().unwrap_or_default(),
             matched_text: det.matched_text.clone(),
-            redacted_text: Some(redacted[det.start..det.end].to_string()),
+            redacted_text: Some(redacted[det.span.start..det.span.end].to_string()),
+            suppression: det.suppression.clone(),
+            fingerprint: det.fingerprint.clone(),
+            rules: det.rules.clone(),
+            origin: None,
         });
     }
     let json = serde_json::to_string_pretty(&results).unwrap();
-    assert!(json.contains("SSN"));
-    assert!(json.contains("MedicalRecordNumber"));
-    assert!(json.contains("IndonesianNIK"));
+    assert!(json.contains("\"ssn\""));
+    assert!(json.contains("\"phi_type\": \"SSN\""));
+    // The labelled MRN is reported once, by `mrn`, not again by `mrn-generic`.
+    assert!(json.contains("\"mrn\""));
+    assert!(!json.contains("\"mrn-generic\""));
+    assert!(json.contains("\"indonesian_nik\""));
+    assert!(json.contains("\"severity\": \"high\""));
 }
 
 #[test]
 fn test_pipeline_summary() {
     let text = "SSN: 123-45-6789, SSN: 987-65-4321";
-    let scanner = Scanner::new(healthwand::phi_patterns::PHIPattern::all_patterns(), 10);
+    let scanner = Scanner::new(builtin_patterns(), 10);
     let detections = scanner.scan(text);
     let mut summary = ResultsSummary::default();
     for det in &detections {
         *summary
             .detections_by_type
-            .entry(det.phi_type.clone())
+            .entry(phi_type(&det.pattern_id))
             .or_insert(0) += 1;
     }
     summary.files_processed += 1;
@@ -45,5 +64,70 @@ fn test_pipeline_summary() {
     assert_eq!(summary.files_processed, 1);
     assert_eq!(summary.total_detections, 2);
     assert_eq!(summary.redacted_count, 2);
-    assert!(summary.detections_by_type.contains_key(&PHIType::SSN));
+    assert!(summary.detections_by_type.contains_key("SSN"));
+    assert!(detections.iter().all(|d| d.severity == Severity::High));
+}
+
+#[test]
+fn test_builtin_icd10_escalation() {
+    let scanner = Scanner::new(builtin_patterns(), 10);
+    let severity = |text: &str| {
+        scanner
+            .scan(text)
+            .into_iter()
+            .find(|d| d.pattern_id.as_str() == "icd10")
+            .map(|d| d.severity)
+    };
+    assert_eq!(
+        severity("Diagnosis: I21.0, acute infarction"),
+        Some(Severity::Critical)
+    );
+    assert_eq!(
+        severity("Pasien NIK 3171234567890123 dirawat dengan I21.0"),
+        Some(Severity::Critical)
+    );
+    assert_eq!(
+        severity("Chapter 9 of the textbook covers I21.0 in depth."),
+        Some(Severity::Informational)
+    );
+}
+
+#[test]
+fn test_repository_globs_apply_from_outside_the_repository() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let repo = dir.path().join("repo");
+    for sub in [".git", "docs", "skip"] {
+        std::fs::create_dir_all(repo.join(sub)).unwrap();
+    }
+    std::fs::write(
+        repo.join(".healthwand.yaml"),
+        "exclude: ['skip/**']\nrules:\n  - paths: ['docs/**']\n    patterns: [ssn]\n    enabled: false\n",
+    )
+    .unwrap();
+    for file in ["notes.txt", "docs/a.txt", "skip/b.txt"] {
+        std::fs::write(repo.join(file), "SSN: 123-45-6789\n").unwrap();
+    }
+
+    for input in [PathBuf::from("repo"), repo.clone()] {
+        let output = Command::new(env!("CARGO_BIN_EXE_healthwand"))
+            .current_dir(dir.path())
+            .env("XDG_CONFIG_HOME", dir.path())
+            .args([
+                "--input".as_ref(),
+                input.as_os_str(),
+                "--output".as_ref(),
+                "json".as_ref(),
+            ])
+            .output()
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let files: Vec<&str> = report["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["file_path"].as_str().unwrap())
+            .collect();
+        assert_eq!(files.len(), 1, "{}: {:?}", input.display(), files);
+        assert!(files[0].ends_with("notes.txt"));
+    }
 }